@@ -0,0 +1,46 @@
+use rustris::bot::{BeamSearchBot, Bot, HeuristicBot};
+use rustris::game_state::GameState;
+use rustris::mode::GameMode;
+use std::env;
+use std::time::Instant;
+
+const SEEDS: [u64; 3] = [1, 2, 3];
+
+// Plays the same seeded games with each bot and prints how far they got
+fn main() {
+  let max_pieces: u32 = env::args()
+    .nth(1)
+    .and_then(|arg| arg.parse().ok())
+    .unwrap_or(500);
+
+  let bots = [
+    (
+      "heuristic",
+      Bot::Heuristic(HeuristicBot {
+        use_hold: true,
+        ..HeuristicBot::default()
+      }),
+    ),
+    ("beam search", Bot::BeamSearch(BeamSearchBot::default())),
+  ];
+
+  for (name, bot) in bots.iter() {
+    for seed in SEEDS {
+      let mut game_state = GameState::with_seed(10, 20, GameMode::default(), seed);
+      let started = Instant::now();
+
+      while !game_state.game_over && game_state.pieces < max_pieces {
+        if !bot.play(&mut game_state) {
+          break;
+        }
+      }
+
+      let elapsed = started.elapsed();
+      let per_move = elapsed.as_secs_f64() * 1000.0 / game_state.pieces.max(1) as f64;
+      println!(
+        "{name:<12} seed {seed}: {} pieces, {} lines, topped out: {}, {per_move:.3} ms/move",
+        game_state.pieces, game_state.lines, game_state.game_over,
+      );
+    }
+  }
+}
@@ -0,0 +1,128 @@
+// Plays games through the C API and checks what comes back. Build and run from the repository
+// root after cargo build --release:
+//   cc examples/c/test_rustris.c -Iinclude -Ltarget/release -l:librustris.a -lm -o target/test_rustris
+//   ./target/test_rustris
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rustris.h"
+
+#define CHECK(condition)                                                            \
+  do {                                                                              \
+    if (!(condition)) {                                                             \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+      exit(1);                                                                      \
+    }                                                                               \
+  } while (0)
+
+static size_t filled_cells(const RustrisGame *game, uint8_t *cells, size_t len) {
+  size_t count = rustris_game_read_board(game, cells, len);
+  size_t filled = 0;
+  for (size_t i = 0; i < count; i++) {
+    if (cells[i] != RUSTRIS_COLOR_EMPTY) {
+      filled++;
+    }
+  }
+  return filled;
+}
+
+// Plays the same inputs on every game: a few moves and a hard drop per piece, with frames between
+static void play(RustrisGame *game, int pieces) {
+  static const uint32_t moves[] = {
+    RUSTRIS_INPUT_MOVE_LEFT, RUSTRIS_INPUT_ROTATE, RUSTRIS_INPUT_MOVE_RIGHT,
+    RUSTRIS_INPUT_MOVE_RIGHT, RUSTRIS_INPUT_HOLD, RUSTRIS_INPUT_MOVE_LEFT,
+  };
+
+  for (int piece = 0; piece < pieces && !rustris_game_is_over(game); piece++) {
+    for (int move = 0; move < piece % 6; move++) {
+      CHECK(rustris_game_apply_input(game, moves[(piece + move) % 6]));
+      CHECK(rustris_game_step_frames(game, 2));
+    }
+    CHECK(rustris_game_apply_input(game, RUSTRIS_INPUT_HARD_DROP));
+  }
+}
+
+int main(void) {
+  CHECK(rustris_game_new(65, 20, 1) == NULL);
+  CHECK(rustris_game_new(6, 20, 1) == NULL);
+  CHECK(rustris_game_new(10, 3, 1) == NULL);
+
+  RustrisGame *game = rustris_game_new(10, 20, 1);
+  CHECK(game != NULL);
+  CHECK(rustris_game_board_width(game) == 10);
+  CHECK(rustris_game_board_height(game) == 20);
+  CHECK(!rustris_game_is_over(game));
+  CHECK(!rustris_game_apply_input(game, 99));
+
+  // Too small a buffer only reports the size
+  uint8_t cells[200];
+  CHECK(rustris_game_read_board(game, cells, 10) == 200);
+  CHECK(filled_cells(game, cells, sizeof cells) == 0);
+
+  RustrisEvent event;
+  CHECK(rustris_game_poll_event(game, &event));
+  CHECK(event.kind == RUSTRIS_EVENT_PIECE_SPAWNED);
+  CHECK(!rustris_game_poll_event(game, &event));
+
+  CHECK(rustris_game_apply_input(game, RUSTRIS_INPUT_HARD_DROP));
+  CHECK(rustris_game_pieces(game) == 1);
+  CHECK(filled_cells(game, cells, sizeof cells) == 4);
+
+  int locked = 0;
+  int spawned = 0;
+  while (rustris_game_poll_event(game, &event)) {
+    if (event.kind == RUSTRIS_EVENT_LOCKED) {
+      locked++;
+      CHECK(event.color != RUSTRIS_COLOR_EMPTY);
+      CHECK(event.y < 20);
+    }
+    if (event.kind == RUSTRIS_EVENT_PIECE_SPAWNED) {
+      spawned++;
+    }
+  }
+  CHECK(locked == 1 && spawned == 1);
+
+  // Gravity and the lock delay alone end the game eventually
+  CHECK(rustris_game_step_frames(game, 60 * 60 * 10));
+  CHECK(rustris_game_is_over(game));
+  int game_over = 0;
+  while (rustris_game_poll_event(game, &event)) {
+    game_over += event.kind == RUSTRIS_EVENT_GAME_OVER;
+  }
+  CHECK(game_over == 1);
+  rustris_game_free(game);
+
+  // The same seed and inputs play out the same game
+  RustrisGame *first = rustris_game_new(10, 20, 7);
+  RustrisGame *second = rustris_game_new(10, 20, 7);
+  play(first, 300);
+  play(second, 300);
+  uint8_t first_cells[200];
+  uint8_t second_cells[200];
+  rustris_game_read_board(first, first_cells, sizeof first_cells);
+  rustris_game_read_board(second, second_cells, sizeof second_cells);
+  CHECK(memcmp(first_cells, second_cells, sizeof first_cells) == 0);
+  CHECK(rustris_game_pieces(first) == rustris_game_pieces(second));
+  CHECK(rustris_game_score(first) == rustris_game_score(second));
+  CHECK(rustris_game_lines(first) == rustris_game_lines(second));
+  CHECK(rustris_game_level(first) == 1);
+  rustris_game_free(first);
+  rustris_game_free(second);
+
+  // The smallest board plays until it tops out
+  RustrisGame *small = rustris_game_new(7, 4, 3);
+  CHECK(small != NULL);
+  play(small, 100);
+  CHECK(rustris_game_is_over(small));
+  rustris_game_free(small);
+
+  // Null handles are ignored
+  rustris_game_free(NULL);
+  CHECK(!rustris_game_step_frames(NULL, 1));
+  CHECK(rustris_game_is_over(NULL));
+  CHECK(!rustris_game_poll_event(NULL, &event));
+
+  printf("All C API checks passed\n");
+  return 0;
+}
@@ -0,0 +1,62 @@
+use rustris::board::Board;
+use rustris::perfect_clear::{pc_chance, solve, PcChanceError};
+use rustris::randomizer::Randomizer;
+use rustris::shape::PieceKind;
+use std::env;
+use std::time::Instant;
+
+// Perfect clear chance from an empty board for a queue like "IOLJSZTIOL" that starts a 7-bag,
+// drawing the rest from 7-bags. The second argument is the height to clear, 4 by default.
+fn main() {
+  let queue: Vec<PieceKind> = env::args()
+    .nth(1)
+    .unwrap_or_else(|| "IOLJSZTIOL".to_string())
+    .chars()
+    .filter_map(PieceKind::from_letter)
+    .collect();
+  let height = env::args()
+    .nth(2)
+    .and_then(|arg| arg.parse().ok())
+    .unwrap_or(4);
+  let board = Board::new(10, 20);
+
+  // What the last, unfinished bag of the queue has left, nothing when the queue ends a bag
+  let last_bag = &queue[queue.len() - queue.len() % 7..];
+  let bag = Randomizer::Bag(
+    PieceKind::ALL
+      .into_iter()
+      .filter(|kind| !last_bag.is_empty() && !last_bag.contains(kind))
+      .collect(),
+  );
+
+  let started = Instant::now();
+  match pc_chance(&board, &queue, None, &bag, height) {
+    Ok(chance) => println!(
+      "{}/{} sequences ({:.2}%) in {:.1?}",
+      chance.solvable,
+      chance.sequences,
+      chance.probability() * 100.0,
+      started.elapsed()
+    ),
+    Err(PcChanceError::NoClear) => println!("No perfect clear {} rows up", height),
+    Err(PcChanceError::TooManyUnknown(unknown)) => {
+      println!(
+        "{} pieces past the queue are needed, too many to look ahead",
+        unknown
+      )
+    }
+  }
+
+  if let Some(solution) = solve(&board, &queue, None, height, 1).first() {
+    for step in solution {
+      println!(
+        "{}{} rotation {} at x {} y {}",
+        if step.hold { "hold, " } else { "" },
+        step.piece.kind.letter(),
+        step.piece.rotation,
+        step.piece.x,
+        step.piece.y
+      );
+    }
+  }
+}
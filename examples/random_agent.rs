@@ -0,0 +1,45 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustris::env::{Action, Env, Reward};
+use rustris::mode::GameMode;
+use std::env;
+use std::time::Instant;
+
+// Plays seeded episodes in the learning environment with random placements and reports how many
+// run per second
+fn main() {
+  let episodes: u64 = env::args()
+    .nth(1)
+    .and_then(|arg| arg.parse().ok())
+    .unwrap_or(1000);
+
+  let mut env = Env::new(10, 20, GameMode::default(), Reward::default());
+  let mut rng = StdRng::seed_from_u64(0);
+  let mut steps = 0;
+  let mut total_reward = 0.0;
+  let started = Instant::now();
+
+  for seed in 0..episodes {
+    env.reset(seed);
+    loop {
+      let action = Action::Place(rng.gen_range(0..env.placements().len()));
+      let step = env
+        .step(action)
+        .expect("Placements are picked from the list");
+      steps += 1;
+      total_reward += step.reward;
+
+      if step.done {
+        break;
+      }
+    }
+  }
+
+  let elapsed = started.elapsed().as_secs_f64();
+  println!(
+    "{episodes} episodes, {steps} steps, {:.2} reward per episode, {:.0} episodes/s, {:.0} steps/s",
+    total_reward / episodes as f32,
+    episodes as f64 / elapsed,
+    steps as f64 / elapsed
+  );
+}
@@ -0,0 +1,167 @@
+#ifndef RUSTRIS_H
+#define RUSTRIS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define RUSTRIS_INPUT_MOVE_LEFT 0
+
+#define RUSTRIS_INPUT_MOVE_RIGHT 1
+
+#define RUSTRIS_INPUT_SOFT_DROP 2
+
+#define RUSTRIS_INPUT_ROTATE 3
+
+#define RUSTRIS_INPUT_HARD_DROP 4
+
+#define RUSTRIS_INPUT_HOLD 5
+
+#define RUSTRIS_COLOR_CYAN 0
+
+#define RUSTRIS_COLOR_YELLOW 1
+
+#define RUSTRIS_COLOR_PURPLE 2
+
+#define RUSTRIS_COLOR_GREEN 3
+
+#define RUSTRIS_COLOR_RED 4
+
+#define RUSTRIS_COLOR_BLUE 5
+
+#define RUSTRIS_COLOR_ORANGE 6
+
+#define RUSTRIS_COLOR_GARBAGE 7
+
+#define RUSTRIS_COLOR_EMPTY 8
+
+#define RUSTRIS_EVENT_PIECE_SPAWNED 0
+
+#define RUSTRIS_EVENT_MOVED 1
+
+#define RUSTRIS_EVENT_ROTATED 2
+
+#define RUSTRIS_EVENT_LOCKED 3
+
+#define RUSTRIS_EVENT_LINES_CLEARED 4
+
+#define RUSTRIS_EVENT_HOLD 5
+
+#define RUSTRIS_EVENT_LEVEL_UP 6
+
+#define RUSTRIS_EVENT_FINESSE_FAULT 7
+
+#define RUSTRIS_EVENT_GAME_OVER 8
+
+#define RUSTRIS_EVENT_COMPLETED 9
+
+#define RUSTRIS_DIRECTION_LEFT 0
+
+#define RUSTRIS_DIRECTION_RIGHT 1
+
+#define RUSTRIS_DIRECTION_DOWN 2
+
+#define RUSTRIS_SPIN_NONE 0
+
+#define RUSTRIS_SPIN_MINI 1
+
+#define RUSTRIS_SPIN_FULL 2
+
+/**
+ * A game in progress, only handled through pointers. Functions given a null game return 0,
+ * false or nothing, and so do they if the engine fails inside the call.
+ */
+typedef struct RustrisGame RustrisGame;
+
+/**
+ * Something that happened in the game. Fields that do not apply to the kind are 0.
+ */
+typedef struct RustrisEvent {
+  /**
+   * One of RUSTRIS_EVENT_*
+   */
+  uint32_t kind;
+  /**
+   * RUSTRIS_COLOR_* of the piece that spawned or locked
+   */
+  uint8_t color;
+  /**
+   * Top-left corner of the locked piece
+   */
+  uint8_t x;
+  uint8_t y;
+  /**
+   * RUSTRIS_DIRECTION_* of a move
+   */
+  uint8_t direction;
+  /**
+   * Lines cleared at once
+   */
+  uint8_t lines;
+  /**
+   * RUSTRIS_SPIN_* of the clear
+   */
+  uint8_t spin;
+  /**
+   * The level reached
+   */
+  uint32_t level;
+  /**
+   * Counted key presses for a piece placed with a finesse fault, and the fewest possible
+   */
+  uint32_t key_presses;
+  uint32_t optimal;
+} RustrisEvent;
+
+/**
+ * Start a Marathon game. The same seed and inputs on the same frames always play out the same
+ * game. Returns null when the board is not 7 to 64 cells wide and at least 4 tall. Free it
+ * with rustris_game_free.
+ */
+struct RustrisGame *rustris_game_new(uint8_t width, uint8_t height, uint64_t seed);
+
+/**
+ * Free a game from rustris_game_new.
+ */
+void rustris_game_free(struct RustrisGame *game);
+
+/**
+ * Press a key, one of RUSTRIS_INPUT_*. Returns false for an unknown input.
+ */
+bool rustris_game_apply_input(struct RustrisGame *game, uint32_t input);
+
+/**
+ * Advance the game by frames at 60 frames per second: gravity, lock delay and the mode's timers.
+ * Returns false for a null game.
+ */
+bool rustris_game_step_frames(struct RustrisGame *game, uint32_t frames);
+
+uint8_t rustris_game_board_width(const struct RustrisGame *game);
+
+uint8_t rustris_game_board_height(const struct RustrisGame *game);
+
+/**
+ * Copy the locked cells row by row from the top into buffer as RUSTRIS_COLOR_* values, without
+ * the falling piece. Returns the number of cells on the board, and copies nothing when len is
+ * smaller than that.
+ */
+size_t rustris_game_read_board(const struct RustrisGame *game, uint8_t *buffer, size_t len);
+
+uint32_t rustris_game_score(const struct RustrisGame *game);
+
+uint32_t rustris_game_lines(const struct RustrisGame *game);
+
+uint32_t rustris_game_level(const struct RustrisGame *game);
+
+uint32_t rustris_game_pieces(const struct RustrisGame *game);
+
+bool rustris_game_is_over(const struct RustrisGame *game);
+
+/**
+ * Take the oldest event not read yet into event. Returns false when there are none left.
+ */
+bool rustris_game_poll_event(struct RustrisGame *game, struct RustrisEvent *event);
+
+#endif  /* RUSTRIS_H */
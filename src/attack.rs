@@ -0,0 +1,154 @@
+use crate::game_event::{ClearInfo, SpinType};
+
+// Garbage sent per clear, following the modern guideline. Tables indexed by a chain length use
+// their last entry for longer chains.
+#[derive(Clone, Debug)]
+pub struct AttackTable {
+  pub lines: [u32; 5],       // Plain clears by line count
+  pub t_spin: [u32; 4],      // T-spin clears by line count
+  pub t_spin_mini: [u32; 3], // T-spin mini clears by line count
+  pub combo: Vec<u32>,       // Bonus by combo count
+  pub b2b: Vec<u32>,         // Bonus by back-to-back level
+  pub perfect_clear: u32,    // Bonus for clearing the whole board
+}
+
+impl AttackTable {
+  // Garbage lines a clear sends, before cancelling against incoming garbage
+  pub fn attack(&self, clear: &ClearInfo) -> u32 {
+    let lines = clear.lines as usize;
+    let base = match clear.spin {
+      SpinType::None => self.lines.get(lines),
+      SpinType::Mini => self.t_spin_mini.get(lines),
+      SpinType::Full => self.t_spin.get(lines),
+    }
+    .copied()
+    .unwrap_or(0);
+
+    let b2b = if clear.is_difficult() {
+      chain_bonus(&self.b2b, clear.b2b)
+    } else {
+      0
+    };
+    let combo = chain_bonus(&self.combo, clear.combo);
+    let perfect_clear = if clear.perfect_clear {
+      self.perfect_clear
+    } else {
+      0
+    };
+
+    base + b2b + combo + perfect_clear
+  }
+}
+
+impl Default for AttackTable {
+  fn default() -> Self {
+    AttackTable {
+      lines: [0, 0, 1, 2, 4],
+      t_spin: [0, 2, 4, 6],
+      t_spin_mini: [0, 0, 1],
+      combo: vec![0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+      b2b: vec![0, 1],
+      perfect_clear: 10,
+    }
+  }
+}
+
+fn chain_bonus(table: &[u32], chain: u32) -> u32 {
+  table
+    .get(chain as usize)
+    .or(table.last())
+    .copied()
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn clear(lines: u8, spin: SpinType) -> ClearInfo {
+    ClearInfo {
+      lines,
+      spin,
+      b2b: 0,
+      combo: 0,
+      perfect_clear: false,
+    }
+  }
+
+  #[test]
+  fn base_attack() {
+    let table = AttackTable::default();
+    let cases = [
+      (1, SpinType::None, 0),
+      (2, SpinType::None, 1),
+      (3, SpinType::None, 2),
+      (4, SpinType::None, 4),
+      (0, SpinType::Full, 0),
+      (1, SpinType::Full, 2),
+      (2, SpinType::Full, 4),
+      (3, SpinType::Full, 6),
+      (1, SpinType::Mini, 0),
+      (2, SpinType::Mini, 1),
+    ];
+
+    for (lines, spin, attack) in cases {
+      assert_eq!(
+        table.attack(&clear(lines, spin)),
+        attack,
+        "{lines} lines, {spin:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn back_to_back() {
+    let table = AttackTable::default();
+    let cases = [
+      (4, SpinType::None, 1, 5),
+      (4, SpinType::None, 7, 5),
+      (2, SpinType::Full, 1, 5),
+      (2, SpinType::Mini, 1, 2),
+      // Only difficult clears get the bonus
+      (3, SpinType::None, 1, 2),
+    ];
+
+    for (lines, spin, b2b, attack) in cases {
+      let clear = ClearInfo {
+        b2b,
+        ..clear(lines, spin)
+      };
+      assert_eq!(
+        table.attack(&clear),
+        attack,
+        "{lines} lines, {spin:?}, b2b {b2b}"
+      );
+    }
+  }
+
+  #[test]
+  fn combo() {
+    let table = AttackTable::default();
+    let singles: Vec<u32> = (0..15)
+      .map(|combo| {
+        table.attack(&ClearInfo {
+          combo,
+          ..clear(1, SpinType::None)
+        })
+      })
+      .collect();
+
+    assert_eq!(singles, [0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5, 5, 5]);
+  }
+
+  #[test]
+  fn perfect_clear() {
+    let table = AttackTable::default();
+    let clear = ClearInfo {
+      perfect_clear: true,
+      combo: 2,
+      ..clear(4, SpinType::None)
+    };
+
+    assert_eq!(table.attack(&clear), 4 + 1 + 10);
+  }
+}
@@ -0,0 +1,45 @@
+use rustris::bot::{BeamSearchBot, Bot, HeuristicBot};
+use rustris::tbp::{BotMessage, FrontendMessage, TbpBot};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+// One of our bots speaking the Tetris Bot Protocol over stdin and stdout:
+// tbp_bot [beam|heuristic] [weights file]
+fn main() -> io::Result<()> {
+  let mut bot = match env::args().nth(1).as_deref() {
+    Some("heuristic") => Bot::Heuristic(HeuristicBot {
+      use_hold: true,
+      ..HeuristicBot::default()
+    }),
+    _ => Bot::BeamSearch(BeamSearchBot::default()),
+  };
+  if let Some(path) = env::args().nth(2) {
+    bot.set_weights(serde_json::from_str(&fs::read_to_string(path)?)?);
+  }
+  let mut tbp_bot = TbpBot::new(bot);
+  let mut stdout = io::stdout().lock();
+  send(&mut stdout, &TbpBot::info())?;
+
+  for line in io::stdin().lock().lines() {
+    // Messages the bot does not understand are ignored, as the protocol asks
+    let Ok(message) = serde_json::from_str::<FrontendMessage>(&line?) else {
+      continue;
+    };
+
+    if message == FrontendMessage::Quit {
+      break;
+    }
+    if let Some(reply) = tbp_bot.handle(message) {
+      send(&mut stdout, &reply)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn send(out: &mut impl Write, message: &BotMessage) -> io::Result<()> {
+  serde_json::to_writer(&mut *out, message)?;
+  writeln!(out)?;
+  out.flush()
+}
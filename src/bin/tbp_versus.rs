@@ -0,0 +1,152 @@
+use rustris::tbp::{play_move, start_message, BotMessage, FrontendMessage};
+use rustris::versus::Versus;
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// Pieces each bot may play before the match is called a draw
+const MAX_PIECES: u32 = 2000;
+
+// Runs two protocol bots against each other on our rules:
+// tbp_versus "<player one command>" "<player two command>"
+fn main() -> io::Result<()> {
+  let commands: Vec<String> = env::args().skip(1).collect();
+  if commands.len() != 2 {
+    eprintln!("usage: tbp_versus \"<player one command>\" \"<player two command>\"");
+    process::exit(2);
+  }
+
+  let mut versus = Versus::new(10, 20);
+  let mut bots = Vec::new();
+
+  for (player, command) in commands.iter().enumerate() {
+    // Without a frame clock garbage rises on the receiver's next piece
+    versus.players[player].garbage.delay_frames = 0;
+
+    let mut bot = BotProcess::spawn(command)?;
+    match bot.receive()? {
+      Some(BotMessage::Info { name, version, .. }) => {
+        println!("Player {}: {name} {version}", player + 1)
+      }
+      _ => return Err(bot_error(player, "did not introduce itself")),
+    }
+
+    bot.send(&FrontendMessage::Rules)?;
+    if bot.receive()? != Some(BotMessage::Ready) {
+      return Err(bot_error(player, "does not support our rules"));
+    }
+
+    bot.send(&start_message(&versus.players[player]))?;
+    bots.push(bot);
+  }
+
+  let mut forfeit = None;
+  'game: while !versus.is_over() && versus.players[0].pieces < MAX_PIECES {
+    for (player, bot) in bots.iter_mut().enumerate() {
+      bot.send(&FrontendMessage::Suggest)?;
+      let suggested = match bot.receive()? {
+        Some(BotMessage::Suggestion { moves }) => moves.first().copied(),
+        _ => None,
+      };
+
+      let result = match suggested {
+        Some(tbp_move) => {
+          versus.update_player(player, |game_state| play_move(game_state, &tbp_move))
+        }
+        None => Err("No move suggested".to_string()),
+      };
+
+      match result {
+        Ok(messages) => {
+          for message in messages.iter() {
+            bot.send(message)?;
+          }
+        }
+        Err(reason) => {
+          println!("Player {} forfeits: {reason}", player + 1);
+          forfeit = Some(player);
+          break 'game;
+        }
+      }
+
+      if versus.is_over() {
+        break 'game;
+      }
+    }
+  }
+
+  for bot in bots.iter_mut() {
+    bot.quit()?;
+  }
+
+  let winner = forfeit.map(|player| 1 - player).or(versus.winner);
+  for (player, game_state) in versus.players.iter().enumerate() {
+    println!(
+      "Player {}: {} pieces, {} lines, {} attack sent",
+      player + 1,
+      game_state.pieces,
+      game_state.lines,
+      game_state.attack_sent
+    );
+  }
+  match winner {
+    Some(player) => println!("Player {} wins", player + 1),
+    None => println!("Draw"),
+  }
+
+  Ok(())
+}
+
+struct BotProcess {
+  child: Child,
+  stdin: ChildStdin,
+  stdout: BufReader<ChildStdout>,
+}
+
+impl BotProcess {
+  fn spawn(command: &str) -> io::Result<Self> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let mut child = Command::new(program)
+      .args(parts)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .spawn()?;
+
+    Ok(BotProcess {
+      stdin: child.stdin.take().unwrap(),
+      stdout: BufReader::new(child.stdout.take().unwrap()),
+      child,
+    })
+  }
+
+  fn send(&mut self, message: &FrontendMessage) -> io::Result<()> {
+    serde_json::to_writer(&mut self.stdin, message)?;
+    writeln!(self.stdin)?;
+    self.stdin.flush()
+  }
+
+  // The next message the bot sends, skipping lines that are not protocol messages. None when
+  // the bot exited.
+  fn receive(&mut self) -> io::Result<Option<BotMessage>> {
+    let mut line = String::new();
+    loop {
+      line.clear();
+      if self.stdout.read_line(&mut line)? == 0 {
+        return Ok(None);
+      }
+      if let Ok(message) = serde_json::from_str(&line) {
+        return Ok(Some(message));
+      }
+    }
+  }
+
+  fn quit(&mut self) -> io::Result<()> {
+    self.send(&FrontendMessage::Quit)?;
+    self.child.wait().map(|_| ())
+  }
+}
+
+fn bot_error(player: usize, problem: &str) -> io::Error {
+  io::Error::other(format!("Player {} {problem}", player + 1))
+}
@@ -0,0 +1,180 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rustris::bot::{Bot, HeuristicBot, Weights};
+use rustris::game_state::GameState;
+use rustris::mode::GameMode;
+use std::env;
+use std::fs;
+use std::io;
+use std::thread;
+
+const POPULATION: usize = 24;
+// The best candidates of a generation, kept as they are and bred into the rest
+const PARENTS: usize = 6;
+const GAMES_PER_CANDIDATE: u64 = 4;
+// Games are cut off here, so strong weights are told apart by score rather than by survival
+const MAX_PIECES: u32 = 500;
+const MUTATION: f32 = 0.1;
+
+// Evolves the heuristic bot's weights by playing seeded games, every candidate on the same
+// seeds, and writes the best ones as JSON for Tetris.load_bot_weights:
+// tune_weights [generations] [output file]
+fn main() -> io::Result<()> {
+  let generations: u32 = env::args()
+    .nth(1)
+    .and_then(|arg| arg.parse().ok())
+    .unwrap_or(20);
+  let output = env::args()
+    .nth(2)
+    .unwrap_or_else(|| "weights.json".to_string());
+  let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+  let mut rng = StdRng::seed_from_u64(0);
+  let mut population: Vec<Weights> = vec![Weights::default()];
+  while population.len() < POPULATION {
+    population.push(mutate(&Weights::default(), 0.5, &mut rng));
+  }
+
+  let mut best = None;
+  for generation in 0..generations {
+    let seeds: Vec<u64> = (0..GAMES_PER_CANDIDATE)
+      .map(|game| generation as u64 * GAMES_PER_CANDIDATE + game)
+      .collect();
+    let mut results = evaluate_all(&population, &seeds, threads);
+    results.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+
+    let (weights, fitness) = results[0];
+    println!(
+      "generation {}: {:.1} lines, {:.0} score, {:.1} pieces, {:.0}% survived with {:?}",
+      generation + 1,
+      fitness.lines,
+      fitness.score,
+      fitness.pieces,
+      fitness.survived * 100.0,
+      weights
+    );
+    best = Some(weights);
+
+    let parents: Vec<Weights> = results[..PARENTS]
+      .iter()
+      .map(|(weights, _)| *weights)
+      .collect();
+    population = parents.clone();
+    while population.len() < POPULATION {
+      let a = parents.choose(&mut rng).unwrap();
+      let b = parents.choose(&mut rng).unwrap();
+      population.push(mutate(&crossover(a, b, &mut rng), MUTATION, &mut rng));
+    }
+  }
+
+  if let Some(weights) = best {
+    fs::write(&output, serde_json::to_string_pretty(&weights)?)?;
+    println!("Wrote {output}");
+  }
+  Ok(())
+}
+
+// How a candidate did, averaged over its games
+#[derive(Clone, Copy, Default)]
+struct Fitness {
+  lines: f32,
+  score: f32,
+  pieces: f32,
+  survived: f32, // Share of games still going when they were cut off
+}
+
+// Every candidate's fitness, with the candidates split between threads
+fn evaluate_all(population: &[Weights], seeds: &[u64], threads: usize) -> Vec<(Weights, Fitness)> {
+  let chunk_size = population.len().div_ceil(threads);
+
+  thread::scope(|scope| {
+    let handles: Vec<_> = population
+      .chunks(chunk_size)
+      .map(|chunk| {
+        scope.spawn(move || {
+          chunk
+            .iter()
+            .map(|weights| (*weights, evaluate(weights, seeds)))
+            .collect::<Vec<_>>()
+        })
+      })
+      .collect();
+
+    handles
+      .into_iter()
+      .flat_map(|handle| handle.join().unwrap())
+      .collect()
+  })
+}
+
+fn evaluate(weights: &Weights, seeds: &[u64]) -> Fitness {
+  let bot = Bot::Heuristic(HeuristicBot {
+    weights: *weights,
+    use_hold: true,
+  });
+  let mut total = Fitness::default();
+
+  for &seed in seeds {
+    let mut game_state = GameState::with_seed(10, 20, GameMode::default(), seed);
+    while !game_state.game_over && game_state.pieces < MAX_PIECES {
+      if !bot.play(&mut game_state) {
+        break;
+      }
+    }
+
+    total.lines += game_state.lines as f32;
+    total.score += game_state.score as f32;
+    total.pieces += game_state.pieces as f32;
+    total.survived += !game_state.game_over as u8 as f32;
+  }
+
+  let games = seeds.len() as f32;
+  Fitness {
+    lines: total.lines / games,
+    score: total.score / games,
+    pieces: total.pieces / games,
+    survived: total.survived / games,
+  }
+}
+
+// Each weight taken from either parent
+fn crossover(a: &Weights, b: &Weights, rng: &mut impl Rng) -> Weights {
+  let a = to_array(a);
+  let b = to_array(b);
+  from_array(std::array::from_fn(|index| {
+    if rng.gen_bool(0.5) {
+      a[index]
+    } else {
+      b[index]
+    }
+  }))
+}
+
+// Every weight nudged by up to spread times its size, or by spread itself for small weights
+fn mutate(weights: &Weights, spread: f32, rng: &mut impl Rng) -> Weights {
+  from_array(
+    to_array(weights)
+      .map(|weight| weight + rng.gen_range(-spread..=spread) * weight.abs().max(spread)),
+  )
+}
+
+fn to_array(weights: &Weights) -> [f32; 5] {
+  [
+    weights.aggregate_height,
+    weights.lines_cleared,
+    weights.holes,
+    weights.bumpiness,
+    weights.wells,
+  ]
+}
+
+fn from_array([aggregate_height, lines_cleared, holes, bumpiness, wells]: [f32; 5]) -> Weights {
+  Weights {
+    aggregate_height,
+    lines_cleared,
+    holes,
+    bumpiness,
+    wells,
+  }
+}
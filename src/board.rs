@@ -1,43 +1,211 @@
-use crate::shape::Color;
+mod metrics;
+
+use crate::piece::Piece;
+use crate::shape::{Color, Shape};
 use std::ops::Range;
 
+// One row of the board as bits, bit x set when column x is filled
+pub type Row = u64;
+
+pub const MAX_WIDTH: u8 = Row::BITS as u8;
+
+// Rows of bits for collisions and line clears, with the colours kept alongside for drawing.
+// Boards that only simulate placements can drop the colours.
+#[derive(Clone)]
 pub struct Board {
   pub width: u8,
   pub height: u8,
   pub cell_size: u8,
-  pub grid: Vec<Vec<Color>>,
+  rows: Vec<Row>,
+  colors: Option<Vec<Vec<Color>>>,
 }
 
 #[allow(deprecated)]
 impl Board {
   pub fn new(width: u8, height: u8) -> Self {
+    assert!(width <= MAX_WIDTH, "boards are at most {} wide", MAX_WIDTH);
+
     Board {
       width,
       height,
       cell_size: 40,
-      grid: vec![vec![Color::None; width as usize]; height as usize], // Initialize entire grid as "None"
+      rows: vec![0; height as usize],
+      // Initialize entire grid as "None"
+      colors: Some(vec![vec![Color::None; width as usize]; height as usize]),
+    }
+  }
+
+  // The same cells without colours, cheaper to clone and update. Filled cells read as garbage.
+  pub fn without_colors(&self) -> Board {
+    Board {
+      colors: None,
+      rows: self.rows.clone(),
+      ..*self
+    }
+  }
+
+  pub fn rows(&self) -> &[Row] {
+    &self.rows
+  }
+
+  // A row with every column filled
+  pub fn full_row(&self) -> Row {
+    Row::MAX >> (MAX_WIDTH - self.width)
+  }
+
+  pub fn is_filled(&self, x: usize, y: usize) -> bool {
+    self.rows[y] >> x & 1 == 1
+  }
+
+  pub fn cell(&self, x: usize, y: usize) -> Color {
+    match &self.colors {
+      Some(colors) => colors[y][x],
+      None if self.is_filled(x, y) => Color::Garbage,
+      None => Color::None,
+    }
+  }
+
+  // Every cell's colour, row by row from the top, as the board used to store them
+  #[deprecated(note = "read cells with cell() or is_filled(), or whole rows with rows()")]
+  pub fn grid(&self) -> Vec<Vec<Color>> {
+    self
+      .iter_height()
+      .map(|y| self.iter_width().map(|x| self.cell(x, y)).collect())
+      .collect()
+  }
+
+  // Replace every cell's colour from rows laid out as grid() returns them
+  #[deprecated(note = "write cells with set_cell()")]
+  pub fn set_grid(&mut self, grid: &[Vec<Color>]) {
+    for (y, row) in grid.iter().enumerate() {
+      for (x, &color) in row.iter().enumerate() {
+        self.set_cell(x, y, color);
+      }
     }
   }
 
-  pub fn clear_full_lines(&mut self) -> u8 {
-    let mut cleared_rows = 0;
+  // Fill or empty a cell, keeping the bits and colours in step. Color::None empties it.
+  pub fn set_cell(&mut self, x: usize, y: usize, color: Color) {
+    if color == Color::None {
+      self.rows[y] &= !(1 << x);
+    } else {
+      self.rows[y] |= 1 << x;
+    }
+
+    if let Some(colors) = &mut self.colors {
+      colors[y][x] = color;
+    }
+  }
+
+  // Whether a shape with its top-left corner at x, y lies inside the board without overlapping
+  // a filled cell. Rows above the board are open.
+  pub fn fits(&self, shape: &Shape, x: i32, y: i32) -> bool {
+    if x < 0 || x + shape.width as i32 > self.width as i32 {
+      return false;
+    }
+    if y + shape.height as i32 > self.height as i32 {
+      return false;
+    }
+
+    shape.iter_height().all(|row| {
+      let board_y = y + row as i32;
+      board_y < 0 || self.rows[board_y as usize] & shape.row_mask(row) << x == 0
+    })
+  }
 
-    // Retain only rows that are not full and count the cleared rows
-    self.grid.retain(|row| {
-      if row.iter().all(|&cell| cell != Color::None) {
-        cleared_rows += 1;
-        false // Remove this row (it's full)
-      } else {
-        true // Keep this row
+  // Write the piece's cells into the grid
+  pub fn merge_piece(&mut self, piece: &Piece) {
+    for y in piece.shape().iter_height() {
+      self.rows[piece.y as usize + y] |= piece.shape().row_mask(y) << piece.x;
+    }
+
+    if let Some(colors) = &mut self.colors {
+      for y in piece.shape().iter_height() {
+        for x in piece.shape().iter_width() {
+          if piece.shape().cells[y][x] == 1 {
+            colors[piece.y as usize + y][piece.x as usize + x] = piece.shape().color;
+          }
+        }
+      }
+    }
+  }
+
+  // Removes full rows and returns their indices, top to bottom
+  pub fn clear_full_lines(&mut self) -> Vec<u8> {
+    let full_row = self.full_row();
+    let full_rows: Vec<u8> = self
+      .iter_height()
+      .filter(|&y| self.rows[y] == full_row)
+      .map(|y| y as u8)
+      .collect();
+
+    if full_rows.is_empty() {
+      return full_rows;
+    }
+
+    // Move the rows that stay down over the cleared ones, bottom up
+    let mut target = self.height as usize;
+    for y in self.iter_height().rev() {
+      if self.rows[y] != full_row {
+        target -= 1;
+        self.rows[target] = self.rows[y];
+        if let Some(colors) = &mut self.colors {
+          colors.swap(target, y);
+        }
       }
-    });
+    }
 
-    // Add empty rows at the top to maintain the board's size
-    for _ in 0..cleared_rows {
-      self.grid.insert(0, vec![Color::None; self.width as usize]);
+    // Empty rows at the top keep the board's size
+    for y in 0..target {
+      self.clear_row(y);
     }
 
-    cleared_rows
+    full_rows
+  }
+
+  // Push every row up by one and fill the bottom row with garbage, leaving one hole.
+  // Returns whether a filled cell was pushed off the top.
+  pub fn push_garbage_row(&mut self, hole: usize) -> bool {
+    let overflowed = self.rows[0] != 0;
+    let hole = hole.min(self.width as usize - 1);
+
+    self.rows.rotate_left(1);
+    *self.rows.last_mut().unwrap() = self.full_row() & !(1 << hole);
+
+    if let Some(colors) = &mut self.colors {
+      colors.rotate_left(1);
+      let row = colors.last_mut().unwrap();
+      row.fill(Color::Garbage);
+      row[hole] = Color::None;
+    }
+
+    overflowed
+  }
+
+  pub fn clear_row(&mut self, row: usize) {
+    self.rows[row] = 0;
+    if let Some(colors) = &mut self.colors {
+      colors[row].fill(Color::None);
+    }
+  }
+
+  pub fn clear(&mut self) {
+    for row in self.iter_height() {
+      self.clear_row(row);
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rows.iter().all(|&row| row == 0)
+  }
+
+  // Rows holding garbage. Without colours filled cells read as garbage, so every row with a
+  // filled cell counts.
+  pub fn garbage_row_count(&self) -> usize {
+    self
+      .iter_height()
+      .filter(|&y| self.iter_width().any(|x| self.cell(x, y) == Color::Garbage))
+      .count()
   }
 
   pub fn iter_height(&self) -> Range<usize> {
@@ -48,3 +216,121 @@ impl Board {
     0..self.width as usize
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shape::PieceKind;
+
+  // A board as rows from the top: a piece letter for its colour, '#' for garbage, '.' for empty
+  fn board(rows: &[&str]) -> Board {
+    let mut board = Board::new(rows[0].len() as u8, rows.len() as u8);
+    for (y, row) in rows.iter().enumerate() {
+      for (x, cell) in row.chars().enumerate() {
+        let color = match cell {
+          '.' => Color::None,
+          '#' => Color::Garbage,
+          letter => PieceKind::from_letter(letter).unwrap().color(),
+        };
+        board.set_cell(x, y, color);
+      }
+    }
+    board
+  }
+
+  // The board's colours in the same layout, checking each against its bit on the way
+  fn rows(board: &Board) -> Vec<String> {
+    board
+      .iter_height()
+      .map(|y| {
+        board
+          .iter_width()
+          .map(|x| {
+            let color = board.cell(x, y);
+            assert_eq!(board.is_filled(x, y), color != Color::None, "{x}, {y}");
+            match color {
+              Color::None => '.',
+              Color::Garbage => '#',
+              color => PieceKind::from_color(color).unwrap().letter(),
+            }
+          })
+          .collect()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn cleared_lines_take_their_colours_with_them() {
+    let mut board = board(&[
+      "...T", //
+      "IIII", //
+      "ZZ.#", //
+      "OOLL", //
+    ]);
+
+    assert_eq!(board.clear_full_lines(), vec![1, 3]);
+    assert_eq!(rows(&board), ["....", "....", "...T", "ZZ.#"]);
+  }
+
+  #[test]
+  fn cleared_lines_without_colours() {
+    let mut board = board(&[
+      "...T", //
+      "IIII", //
+      "ZZ.#", //
+      "OOLL", //
+    ])
+    .without_colors();
+
+    assert_eq!(board.clear_full_lines(), vec![1, 3]);
+    assert_eq!(rows(&board), ["....", "....", "...#", "##.#"]);
+  }
+
+  #[test]
+  fn garbage_pushes_the_stack_up() {
+    let mut board = board(&[
+      "....", //
+      "..T.", //
+      "TTT.", //
+    ]);
+
+    assert!(!board.push_garbage_row(3));
+    assert_eq!(rows(&board), ["..T.", "TTT.", "###."]);
+
+    // The T's top reaches the top row, so the next row pushes it off
+    assert!(board.push_garbage_row(0));
+    assert_eq!(rows(&board), ["TTT.", "###.", ".###"]);
+  }
+
+  #[test]
+  fn garbage_holes_stay_on_the_board() {
+    let mut board = Board::new(4, 2);
+    assert!(!board.push_garbage_row(9));
+    assert_eq!(rows(&board), ["....", "###."]);
+  }
+
+  #[test]
+  fn garbage_rows_without_colours() {
+    let board = board(&[
+      "....", //
+      ".TTT", //
+      "###.", //
+    ]);
+
+    assert_eq!(board.garbage_row_count(), 1);
+    assert_eq!(board.without_colors().garbage_row_count(), 2);
+  }
+
+  #[test]
+  #[allow(deprecated)]
+  fn grids_round_trip() {
+    let original = board(&[
+      "...T", //
+      "ZZ.#", //
+    ]);
+    let mut copy = Board::new(4, 2);
+    copy.set_grid(&original.grid());
+    assert_eq!(rows(&copy), rows(&original));
+    assert_eq!(copy.rows(), original.rows());
+  }
+}
@@ -0,0 +1,282 @@
+use crate::board::{Board, Row};
+use crate::game_event::SpinType;
+use crate::piece::{Direction, Piece};
+use crate::shape::PieceKind;
+
+// Measurements of the stack, all worked out on the bit rows. Heights count up from the floor.
+impl Board {
+  // Height of the highest filled cell in every column, 0 for an empty column
+  pub fn column_heights(&self) -> Vec<u32> {
+    let mut heights = vec![0; self.width as usize];
+    let mut seen: Row = 0;
+
+    for (y, &row) in self.rows.iter().enumerate() {
+      let mut tops = row & !seen;
+      while tops != 0 {
+        heights[tops.trailing_zeros() as usize] = self.height as u32 - y as u32;
+        tops &= tops - 1;
+      }
+      seen |= row;
+    }
+
+    heights
+  }
+
+  pub fn aggregate_height(&self) -> u32 {
+    self.column_heights().iter().sum()
+  }
+
+  // Height of the tallest column
+  pub fn max_height(&self) -> u32 {
+    self
+      .rows
+      .iter()
+      .position(|&row| row != 0)
+      .map_or(0, |y| self.height as u32 - y as u32)
+  }
+
+  // Empty cells with a filled cell somewhere above them
+  pub fn holes(&self) -> u32 {
+    let mut above: Row = 0;
+    let mut holes = 0;
+
+    for &row in self.rows.iter() {
+      holes += (above & !row).count_ones();
+      above |= row;
+    }
+
+    holes
+  }
+
+  // Filled cells with a hole somewhere below them
+  pub fn covered_cells(&self) -> u32 {
+    let full_row = self.full_row();
+    let mut empty_below: Row = 0;
+    let mut covered = 0;
+
+    for &row in self.rows.iter().rev() {
+      covered += (row & empty_below).count_ones();
+      empty_below |= !row & full_row;
+    }
+
+    covered
+  }
+
+  // Summed height difference between neighbouring columns
+  pub fn bumpiness(&self) -> u32 {
+    self
+      .column_heights()
+      .windows(2)
+      .map(|pair| pair[0].abs_diff(pair[1]))
+      .sum()
+  }
+
+  // How far every column sits below the lower of its neighbours, walls counting as the full
+  // height of the board
+  pub fn well_depths(&self) -> Vec<u32> {
+    let heights = self.column_heights();
+    let wall = self.height as u32;
+
+    (0..heights.len())
+      .map(|x| {
+        let left = if x == 0 { wall } else { heights[x - 1] };
+        let right = heights.get(x + 1).copied().unwrap_or(wall);
+        left.min(right).saturating_sub(heights[x])
+      })
+      .collect()
+  }
+
+  // Changes between filled and empty along every row that holds a filled cell, walls counting
+  // as filled
+  pub fn row_transitions(&self) -> u32 {
+    let walls = 1 | 1 << (self.width as u32 + 1);
+
+    self
+      .rows
+      .iter()
+      .filter(|&&row| row != 0)
+      .map(|&row| {
+        let cells = (row as u128) << 1 | walls;
+        ((cells ^ cells >> 1) & !(u128::MAX << (self.width as u32 + 1))).count_ones()
+      })
+      .sum()
+  }
+
+  // Changes between filled and empty down every column, from the open space above the board to
+  // the floor, which counts as filled
+  pub fn column_transitions(&self) -> u32 {
+    let full_row = self.full_row();
+    let mut above: Row = 0;
+    let mut transitions = 0;
+
+    for &row in self.rows.iter().chain([full_row].iter()) {
+      transitions += (above ^ row).count_ones();
+      above = row;
+    }
+
+    transitions
+  }
+
+  // T pieces resting where locking would score a full T-spin and clear at least one line.
+  // Whether a piece can get there is not checked.
+  pub fn t_slots(&self) -> Vec<Piece> {
+    (0..4)
+      .flat_map(|rotation| {
+        let shape = PieceKind::T.shape(rotation);
+        // Rotations that do not fit on the board at all are skipped
+        let max_y = self.height.checked_sub(shape.height);
+        let max_x = self.width.checked_sub(shape.width);
+        max_y
+          .zip(max_x)
+          .into_iter()
+          .flat_map(move |(max_y, max_x)| {
+            (0..=max_y).flat_map(move |y| {
+              (0..=max_x).map(move |x| Piece {
+                kind: PieceKind::T,
+                rotation,
+                x,
+                y,
+              })
+            })
+          })
+      })
+      .filter(|piece| piece.can_stay(self) && !piece.can_move(Direction::Down, self))
+      .filter(|piece| piece.t_spin(self) == SpinType::Full)
+      .filter(|piece| self.lines_cleared_by(piece) > 0)
+      .collect()
+  }
+
+  // The column of a well at least four rows deep with full rows on either side, where a
+  // vertical I piece would clear four lines
+  pub fn tetris_ready(&self) -> Option<usize> {
+    let heights = self.column_heights();
+
+    self.iter_width().find(|&x| {
+      let bottom = self.height as usize - heights[x] as usize;
+      let well_row = self.full_row() & !(1 << x);
+      bottom >= 4
+        && self.rows[bottom - 4..bottom]
+          .iter()
+          .all(|&row| row == well_row)
+    })
+  }
+
+  // Rows the piece would complete if it locked where it is
+  pub fn lines_cleared_by(&self, piece: &Piece) -> u32 {
+    let shape = piece.shape();
+    shape
+      .iter_height()
+      .filter(|&y| {
+        self.rows[piece.y as usize + y] | shape.row_mask(y) << piece.x == self.full_row()
+      })
+      .count() as u32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shape::Color;
+
+  // A board as rows from the top, '#' for a filled cell
+  fn board(rows: &[&str]) -> Board {
+    let mut board = Board::new(rows[0].len() as u8, rows.len() as u8);
+    for (y, row) in rows.iter().enumerate() {
+      for (x, cell) in row.chars().enumerate() {
+        if cell == '#' {
+          board.set_cell(x, y, Color::Garbage);
+        }
+      }
+    }
+    board
+  }
+
+  fn stairs() -> Board {
+    board(&[
+      "....", //
+      ".#..", //
+      ".#.#", //
+      "##.#", //
+    ])
+  }
+
+  fn overhangs() -> Board {
+    board(&[
+      ".#..", //
+      "#.#.", //
+      "....", //
+      "##.#", //
+    ])
+  }
+
+  #[test]
+  fn heights() {
+    let board = stairs();
+    assert_eq!(board.column_heights(), vec![1, 3, 0, 2]);
+    assert_eq!(board.aggregate_height(), 6);
+    assert_eq!(board.max_height(), 3);
+    assert_eq!(Board::new(4, 4).column_heights(), vec![0; 4]);
+  }
+
+  #[test]
+  fn holes_and_covered_cells() {
+    assert_eq!(stairs().holes(), 0);
+    assert_eq!(stairs().covered_cells(), 0);
+    assert_eq!(overhangs().holes(), 5);
+    assert_eq!(overhangs().covered_cells(), 3);
+  }
+
+  #[test]
+  fn bumpiness_and_wells() {
+    let board = stairs();
+    assert_eq!(board.bumpiness(), 7);
+    assert_eq!(board.well_depths(), vec![2, 0, 2, 0]);
+    assert_eq!(Board::new(3, 4).well_depths(), vec![0, 0, 0]);
+    assert_eq!(Board::new(1, 4).well_depths(), vec![4]);
+  }
+
+  #[test]
+  fn transitions() {
+    assert_eq!(stairs().row_transitions(), 10);
+    assert_eq!(stairs().column_transitions(), 4);
+    assert_eq!(overhangs().column_transitions(), 10);
+    assert_eq!(Board::new(4, 4).row_transitions(), 0);
+    assert_eq!(Board::new(4, 4).column_transitions(), 4);
+  }
+
+  #[test]
+  fn t_slots() {
+    let board = board(&[
+      ".....", //
+      ".#...", //
+      "#...#", //
+      "##.##", //
+    ]);
+    let slots: Vec<_> = board
+      .t_slots()
+      .iter()
+      .map(|piece| (piece.rotation, piece.x, piece.y))
+      .collect();
+    // The T pointing down for a double, and pointing left under the overhang for a single
+    assert_eq!(slots, vec![(2, 1, 2), (3, 1, 1)]);
+
+    assert!(Board::new(10, 4).t_slots().is_empty());
+    assert!(Board::new(10, 2).t_slots().is_empty());
+    assert!(Board::new(2, 10).t_slots().is_empty());
+  }
+
+  #[test]
+  fn tetris_ready() {
+    let well = [
+      "....", //
+      "#.##", //
+      "#.##", //
+      "#.##", //
+      "#.##", //
+    ];
+    assert_eq!(board(&well).tetris_ready(), Some(1));
+    assert_eq!(board(&well[1..]).tetris_ready(), Some(1));
+    assert_eq!(board(&well[2..]).tetris_ready(), None);
+    assert_eq!(stairs().tetris_ready(), None);
+  }
+}
@@ -0,0 +1,199 @@
+mod beam_search;
+mod heuristic;
+
+pub use beam_search::BeamSearchBot;
+pub use heuristic::HeuristicBot;
+
+use crate::board::Board;
+use crate::game_state::GameState;
+use crate::input::Input;
+use crate::movegen::LockedPlacement;
+use crate::pathfind::{find_path, path_inputs, KeyPress};
+use crate::piece::Piece;
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+// Frames between placements when the bot autoplays a live game, slow enough to follow
+pub const DEFAULT_MOVE_FRAMES: u64 = 20;
+
+// The bots that can play a game. Every bot ends up playing through the same inputs as a player.
+#[derive(Clone)]
+pub enum Bot {
+  Heuristic(HeuristicBot),
+  BeamSearch(BeamSearchBot),
+}
+
+impl Bot {
+  pub fn best_placement(&self, game_state: &GameState) -> Option<Placement> {
+    match self {
+      Bot::Heuristic(bot) => bot.best_placement(game_state),
+      Bot::BeamSearch(bot) => bot.best_placement(game_state),
+    }
+  }
+
+  // The inputs for the best placement, empty when the piece has nowhere to go
+  pub fn inputs(&self, game_state: &GameState) -> Vec<Input> {
+    self
+      .best_placement(game_state)
+      .map_or_else(Vec::new, |placement| {
+        placement_inputs(game_state, &placement)
+      })
+  }
+
+  // Find and play the best placement. Returns false when the piece has nowhere to go or the game
+  // is already over.
+  pub fn play(&self, game_state: &mut GameState) -> bool {
+    if game_state.game_over {
+      return false;
+    }
+
+    let path = self
+      .best_placement(game_state)
+      .map_or_else(Vec::new, |placement| placement_path(game_state, &placement));
+    for &key_press in path.iter() {
+      game_state.apply_key_press(key_press);
+    }
+
+    !path.is_empty()
+  }
+
+  pub fn weights(&self) -> Weights {
+    match self {
+      Bot::Heuristic(bot) => bot.weights,
+      Bot::BeamSearch(bot) => bot.weights,
+    }
+  }
+
+  pub fn set_weights(&mut self, weights: Weights) {
+    match self {
+      Bot::Heuristic(bot) => bot.weights = weights,
+      Bot::BeamSearch(bot) => bot.weights = weights,
+    }
+  }
+
+  pub fn set_use_hold(&mut self, use_hold: bool) {
+    match self {
+      Bot::Heuristic(bot) => bot.use_hold = use_hold,
+      Bot::BeamSearch(bot) => bot.use_hold = use_hold,
+    }
+  }
+}
+
+impl Default for Bot {
+  fn default() -> Self {
+    Bot::Heuristic(HeuristicBot::default())
+  }
+}
+
+// How much each board feature counts towards a placement's score. Positive weights reward a
+// feature, negative ones penalise it. Saved as JSON, as the weight tuner writes them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+  pub aggregate_height: f32,
+  pub lines_cleared: f32,
+  pub holes: f32,
+  pub bumpiness: f32,
+  pub wells: f32,
+}
+
+impl Weights {
+  // Score of the stack left behind, without the lines it took to get there
+  pub fn score_board(&self, board: &Board) -> f32 {
+    let well_depth: u32 = board.well_depths().iter().sum();
+
+    self.aggregate_height * board.aggregate_height() as f32
+      + self.holes * board.holes() as f32
+      + self.bumpiness * board.bumpiness() as f32
+      + self.wells * well_depth as f32
+  }
+}
+
+impl Default for Weights {
+  fn default() -> Self {
+    Weights {
+      aggregate_height: -0.510066,
+      lines_cleared: 0.760666,
+      holes: -0.35663,
+      bumpiness: -0.184483,
+      wells: -0.1,
+    }
+  }
+}
+
+// Where to put a piece, optionally holding first
+#[derive(Clone)]
+pub struct Placement {
+  pub hold: bool,
+  pub target: LockedPlacement,
+  pub score: f32,
+}
+
+// The piece put into play after holding, placed where hold_piece would put it
+pub fn piece_after_hold(game_state: &GameState) -> Option<Piece> {
+  match &game_state.held_piece {
+    Some(held_piece) => spawned(held_piece, &game_state.board),
+    None => Some(game_state.next_piece.clone()),
+  }
+}
+
+// The piece moved back to the top of the board, None if it has no room there
+pub fn spawned(piece: &Piece, board: &Board) -> Option<Piece> {
+  let mut piece = piece.clone();
+  piece.x = board.width / 2 - piece.shape().width / 2;
+  piece.y = 0;
+
+  piece.can_stay(board).then_some(piece)
+}
+
+// The inputs that play the placement from the current game state, along the path with the
+// fewest key presses
+pub fn placement_inputs(game_state: &GameState, placement: &Placement) -> Vec<Input> {
+  let path = placement_path(game_state, placement);
+  let Some(piece) = placed_piece(game_state, placement) else {
+    return Vec::new();
+  };
+  if path.is_empty() {
+    return Vec::new();
+  }
+
+  if placement.hold {
+    let inputs = path_inputs(&piece, &game_state.board, &path[1..]);
+    iter::once(Input::Hold).chain(inputs).collect()
+  } else {
+    path_inputs(&piece, &game_state.board, &path)
+  }
+}
+
+// The fewest key presses that play a placement, pressing hold first when it asks for it. Empty
+// when there is no way there.
+pub fn placement_path(game_state: &GameState, placement: &Placement) -> Vec<KeyPress> {
+  let Some(piece) = placed_piece(game_state, placement) else {
+    return Vec::new();
+  };
+  let Some(path) = find_path(&piece, &game_state.board, &placement.target) else {
+    return Vec::new();
+  };
+
+  if placement.hold {
+    iter::once(KeyPress::Tap(Input::Hold)).chain(path).collect()
+  } else {
+    path
+  }
+}
+
+fn placed_piece(game_state: &GameState, placement: &Placement) -> Option<Piece> {
+  if placement.hold {
+    piece_after_hold(game_state)
+  } else {
+    Some(game_state.current_piece.clone())
+  }
+}
+
+// The board after locking the piece, with the number of lines it cleared
+fn lock(board: &Board, piece: &Piece) -> (Board, usize) {
+  let mut board = board.clone();
+  board.merge_piece(piece);
+  let lines_cleared = board.clear_full_lines().len();
+
+  (board, lines_cleared)
+}
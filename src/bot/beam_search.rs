@@ -0,0 +1,193 @@
+use crate::board::Board;
+use crate::bot::{lock, spawned, Placement, Weights};
+use crate::game_state::GameState;
+use crate::movegen::locked_placements;
+use crate::piece::Piece;
+use std::iter;
+
+// Looks ahead through the hold piece, the next piece and the preview, keeping only the `width`
+// most promising boards after each piece
+#[derive(Clone)]
+pub struct BeamSearchBot {
+  pub weights: Weights,
+  pub use_hold: bool,
+  pub width: usize,
+  pub depth: usize,     // Pieces to look ahead, counting the current one
+  pub max_nodes: usize, // Placements evaluated per move before settling for the best so far
+  pub time_budget_millis: Option<f64>,
+}
+
+// A board reached by playing some pieces from the queue
+struct Node {
+  board: Board,
+  held_piece: Option<Piece>,
+  queue_index: usize, // The piece to play next
+  hold_available: bool,
+  lines_value: f32, // Reward for the lines cleared on the way here
+  score: f32,
+  first: Option<Placement>, // The placement of the current piece that leads here
+}
+
+impl BeamSearchBot {
+  pub fn best_placement(&self, game_state: &GameState) -> Option<Placement> {
+    let started = now_millis();
+    let queue: Vec<Piece> = iter::once(&game_state.current_piece)
+      .chain(iter::once(&game_state.next_piece))
+      .chain(game_state.preview.iter())
+      .cloned()
+      .collect();
+
+    let mut beam = vec![Node {
+      board: game_state.board.without_colors(),
+      held_piece: game_state.held_piece.clone(),
+      queue_index: 0,
+      hold_available: !game_state.hold_used,
+      lines_value: 0.0,
+      score: 0.0,
+      first: None,
+    }];
+    let mut best = None;
+    let mut nodes = 0;
+
+    for _ in 0..self.depth.max(1) {
+      let mut children = Vec::new();
+      let mut out_of_budget = false;
+
+      for node in beam.iter() {
+        if nodes >= self.max_nodes
+          || self
+            .time_budget_millis
+            .is_some_and(|budget| now_millis() - started >= budget)
+        {
+          out_of_budget = true;
+          break;
+        }
+
+        nodes += self.expand(node, &queue, &mut children);
+      }
+
+      // A partly searched layer is only worth using when nothing better was found yet
+      if children.is_empty() || (out_of_budget && best.is_some()) {
+        break;
+      }
+
+      children.sort_by(|a, b| b.score.total_cmp(&a.score));
+      children.truncate(self.width.max(1));
+
+      best = children[0].first.clone().map(|first| Placement {
+        score: children[0].score,
+        ..first
+      });
+      beam = children;
+    }
+
+    best
+  }
+
+  // Add the boards reachable from the node with its next piece, or the hold piece instead.
+  // Returns how many placements were evaluated.
+  fn expand(&self, node: &Node, queue: &[Piece], children: &mut Vec<Node>) -> usize {
+    let Some(piece) = queue.get(node.queue_index) else {
+      return 0;
+    };
+
+    let mut evaluated = self.expand_piece(
+      node,
+      piece,
+      node.held_piece.clone(),
+      node.queue_index + 1,
+      false,
+      children,
+    );
+
+    if self.use_hold && node.hold_available {
+      // Holding with an empty hold plays the piece after instead
+      let (hold_piece, next_index) = match &node.held_piece {
+        Some(held_piece) => (spawned(held_piece, &node.board), node.queue_index + 1),
+        None => (
+          queue.get(node.queue_index + 1).cloned(),
+          node.queue_index + 2,
+        ),
+      };
+
+      if let Some(hold_piece) = hold_piece {
+        evaluated += self.expand_piece(
+          node,
+          &hold_piece,
+          Some(piece.clone()),
+          next_index,
+          true,
+          children,
+        );
+      }
+    }
+
+    evaluated
+  }
+
+  fn expand_piece(
+    &self,
+    node: &Node,
+    piece: &Piece,
+    held_piece: Option<Piece>,
+    next_index: usize,
+    hold: bool,
+    children: &mut Vec<Node>,
+  ) -> usize {
+    // The piece could not spawn, this line of play tops out
+    if !piece.can_stay(&node.board) {
+      return 0;
+    }
+
+    let options = locked_placements(piece, &node.board);
+    let evaluated = options.len();
+    for target in options {
+      let (board, lines_cleared) = lock(&node.board, &target.piece);
+      let lines_value = node.lines_value + self.weights.lines_cleared * lines_cleared as f32;
+      let score = lines_value + self.weights.score_board(&board);
+
+      children.push(Node {
+        board,
+        held_piece: held_piece.clone(),
+        queue_index: next_index,
+        hold_available: true,
+        lines_value,
+        score,
+        first: node.first.clone().or(Some(Placement {
+          hold,
+          target,
+          score,
+        })),
+      });
+    }
+
+    evaluated
+  }
+}
+
+impl Default for BeamSearchBot {
+  fn default() -> Self {
+    BeamSearchBot {
+      weights: Weights::default(),
+      use_hold: true,
+      width: 8,
+      depth: 3,
+      max_nodes: 20_000,
+      time_budget_millis: None,
+    }
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_millis() -> f64 {
+  web_sys::js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_millis() -> f64 {
+  use std::time::{SystemTime, UNIX_EPOCH};
+
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0.0, |elapsed| elapsed.as_secs_f64() * 1000.0)
+}
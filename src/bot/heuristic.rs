@@ -0,0 +1,50 @@
+use crate::board::Board;
+use crate::bot::{lock, piece_after_hold, Placement, Weights};
+use crate::game_state::GameState;
+use crate::movegen::locked_placements;
+use crate::piece::Piece;
+
+// Greedy bot that scores every placement of the current piece, and of the hold piece when
+// allowed, and plays the best one
+#[derive(Clone, Default)]
+pub struct HeuristicBot {
+  pub weights: Weights,
+  pub use_hold: bool,
+}
+
+impl HeuristicBot {
+  pub fn best_placement(&self, game_state: &GameState) -> Option<Placement> {
+    let board = game_state.board.without_colors();
+    let mut best = self.best_for_piece(&game_state.current_piece, false, &board);
+
+    if self.use_hold && !game_state.hold_used {
+      if let Some(piece) = piece_after_hold(game_state) {
+        let hold_best = self.best_for_piece(&piece, true, &board);
+        if hold_best
+          .as_ref()
+          .is_some_and(|hold| best.as_ref().is_none_or(|best| hold.score > best.score))
+        {
+          best = hold_best;
+        }
+      }
+    }
+
+    best
+  }
+
+  pub fn evaluate(&self, board: &Board, piece: &Piece) -> f32 {
+    let (board, lines_cleared) = lock(board, piece);
+    self.weights.lines_cleared * lines_cleared as f32 + self.weights.score_board(&board)
+  }
+
+  fn best_for_piece(&self, piece: &Piece, hold: bool, board: &Board) -> Option<Placement> {
+    locked_placements(piece, board)
+      .into_iter()
+      .map(|target| Placement {
+        hold,
+        score: self.evaluate(board, &target.piece),
+        target,
+      })
+      .max_by(|a, b| a.score.total_cmp(&b.score))
+  }
+}
@@ -0,0 +1,310 @@
+use crate::bot::{piece_after_hold, placement_path, Placement};
+use crate::game_event::GameEvent;
+use crate::game_state::GameState;
+use crate::input::Input;
+use crate::mode::GameMode;
+use crate::movegen::locked_placements;
+use crate::piece::Piece;
+use crate::shape::PieceKind;
+use std::cell::OnceCell;
+
+// What an agent does on one step. Every step ends by advancing the game one frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  Input(Input), // A single key press
+  Place(usize), // Play one of Env::placements along its shortest path
+  Wait,         // Let the frame pass without pressing anything
+}
+
+// How much each change during a step counts towards its reward
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reward {
+  pub score: f32,     // Per point of score gained
+  pub lines: f32,     // Per line cleared
+  pub pieces: f32,    // Per piece locked
+  pub attack: f32,    // Per garbage line sent
+  pub holes: f32,     // Per hole added to the board, negative to penalise them
+  pub game_over: f32, // Once when the game tops out, but not when the mode is completed
+}
+
+impl Default for Reward {
+  fn default() -> Self {
+    Reward {
+      score: 0.0,
+      lines: 1.0,
+      pieces: 0.0,
+      attack: 0.0,
+      holes: 0.0,
+      game_over: -1.0,
+    }
+  }
+}
+
+// The game as flat numbers, 1.0 where a cell is filled or a piece kind is present. Piece kinds
+// are one-hot in PieceKind::ALL order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+  pub board: Vec<f32>, // Locked cells row by row from the top, without the falling piece
+  pub current: Vec<f32>, // Kind, then rotation, x and y of the falling piece
+  pub hold: Vec<f32>,  // Kind of the held piece, all zero when empty, then whether hold is used
+  pub queue: Vec<f32>, // Kinds of the next and preview pieces, one after the other
+}
+
+impl Observation {
+  pub fn new(game_state: &GameState) -> Self {
+    let board = &game_state.board;
+    let piece = &game_state.current_piece;
+
+    let mut current = one_hot(Some(piece));
+    current.extend([piece.rotation as f32, piece.x as f32, piece.y as f32]);
+
+    let mut hold = one_hot(game_state.held_piece.as_ref());
+    hold.push(game_state.hold_used as u8 as f32);
+
+    Observation {
+      board: board
+        .iter_height()
+        .flat_map(|y| {
+          board
+            .iter_width()
+            .map(move |x| board.is_filled(x, y) as u8 as f32)
+        })
+        .collect(),
+      current,
+      hold,
+      queue: [&game_state.next_piece]
+        .into_iter()
+        .chain(&game_state.preview)
+        .flat_map(|piece| one_hot(Some(piece)))
+        .collect(),
+    }
+  }
+
+  // Everything in one array: board, current, hold, then queue
+  pub fn to_vec(&self) -> Vec<f32> {
+    [&self.board, &self.current, &self.hold, &self.queue]
+      .into_iter()
+      .flatten()
+      .copied()
+      .collect()
+  }
+}
+
+// What a step led to
+#[derive(Clone, Debug)]
+pub struct Step {
+  pub observation: Observation,
+  pub reward: f32,
+  pub done: bool,             // The game is over, reset to play again
+  pub events: Vec<GameEvent>, // Everything that happened during the step
+}
+
+// Why Env::step turned an action down. The game is left as it was, without advancing a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepError {
+  NoPlacement(usize), // Action::Place past the end of placements, which is empty after game over
+}
+
+// A headless game for reinforcement learning. The same seed and actions always play out the
+// same episode.
+pub struct Env {
+  pub board_width: u8,
+  pub board_height: u8,
+  pub mode: GameMode,
+  pub reward: Reward,
+  pub game_state: GameState,
+  placements: OnceCell<Vec<Placement>>, // Listed on first use after the game changes
+}
+
+impl Env {
+  pub fn new(board_width: u8, board_height: u8, mode: GameMode, reward: Reward) -> Self {
+    let game_state = GameState::with_seed(board_width, board_height, mode.clone(), 0);
+
+    let mut env = Env {
+      board_width,
+      board_height,
+      mode,
+      reward,
+      game_state,
+      placements: OnceCell::new(),
+    };
+    env.reset(0);
+    env
+  }
+
+  // Start a new episode
+  pub fn reset(&mut self, seed: u64) -> Observation {
+    self.game_state =
+      GameState::with_seed(self.board_width, self.board_height, self.mode.clone(), seed);
+    self.game_state.drain_events();
+    self.placements.take();
+
+    Observation::new(&self.game_state)
+  }
+
+  // Where the current piece can lock, and the held or next piece after holding, as the choices
+  // for Action::Place. Empty once the game is over.
+  pub fn placements(&self) -> &[Placement] {
+    self
+      .placements
+      .get_or_init(|| list_placements(&self.game_state))
+  }
+
+  // Play the action and advance one frame
+  pub fn step(&mut self, action: Action) -> Result<Step, StepError> {
+    let before = Totals::new(&self.game_state);
+
+    match action {
+      Action::Input(input) => self.game_state.apply_input(input),
+      Action::Place(index) => {
+        let placement = self
+          .placements()
+          .get(index)
+          .ok_or(StepError::NoPlacement(index))?;
+        for key_press in placement_path(&self.game_state, placement) {
+          self.game_state.apply_key_press(key_press);
+        }
+      }
+      Action::Wait => {}
+    }
+    self.game_state.step_frame();
+
+    let after = Totals::new(&self.game_state);
+    let topped_out = after.game_over && !before.game_over && !self.game_state.completed;
+    let reward = self.reward.score * (after.score - before.score) as f32
+      + self.reward.lines * (after.lines - before.lines) as f32
+      + self.reward.pieces * (after.pieces - before.pieces) as f32
+      + self.reward.attack * (after.attack - before.attack) as f32
+      + self.reward.holes * (after.holes as f32 - before.holes as f32)
+      + if topped_out {
+        self.reward.game_over
+      } else {
+        0.0
+      };
+
+    self.placements.take();
+    Ok(Step {
+      observation: Observation::new(&self.game_state),
+      reward,
+      done: self.game_state.game_over,
+      events: self.game_state.drain_events(),
+    })
+  }
+}
+
+// The counters a reward is measured on
+struct Totals {
+  score: u32,
+  lines: u32,
+  pieces: u32,
+  attack: u32,
+  holes: u32,
+  game_over: bool,
+}
+
+impl Totals {
+  fn new(game_state: &GameState) -> Self {
+    Totals {
+      score: game_state.score,
+      lines: game_state.lines,
+      pieces: game_state.pieces,
+      attack: game_state.attack_sent,
+      holes: game_state.board.holes(),
+      game_over: game_state.game_over,
+    }
+  }
+}
+
+// Where the current piece can lock, then where the held or next piece can after holding
+fn list_placements(game_state: &GameState) -> Vec<Placement> {
+  let mut placements = Vec::new();
+  if game_state.game_over {
+    return placements;
+  }
+
+  let board = game_state.board.without_colors();
+  let mut add = |piece: &Piece, hold: bool| {
+    placements.extend(
+      locked_placements(piece, &board)
+        .into_iter()
+        .map(|target| Placement {
+          hold,
+          target,
+          score: 0.0,
+        }),
+    );
+  };
+
+  add(&game_state.current_piece, false);
+  if !game_state.hold_used {
+    if let Some(piece) = piece_after_hold(game_state) {
+      add(&piece, true);
+    }
+  }
+  placements
+}
+
+fn one_hot(piece: Option<&Piece>) -> Vec<f32> {
+  PieceKind::ALL
+    .iter()
+    .map(|&kind| piece.is_some_and(|piece| piece.kind == kind) as u8 as f32)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::rngs::StdRng;
+  use rand::{Rng, SeedableRng};
+
+  fn env() -> Env {
+    Env::new(10, 20, GameMode::default(), Reward::default())
+  }
+
+  #[test]
+  fn placements_past_the_end_are_errors() {
+    let mut env = env();
+    let count = env.placements().len();
+    let frame = env.game_state.frame;
+    let observation = Observation::new(&env.game_state);
+
+    let result = env.step(Action::Place(count));
+    assert_eq!(result.unwrap_err(), StepError::NoPlacement(count));
+    assert_eq!(env.game_state.frame, frame);
+    assert_eq!(Observation::new(&env.game_state), observation);
+
+    // There is nothing to place once the game is over
+    while !env.step(Action::Input(Input::HardDrop)).unwrap().done {}
+    assert!(env.placements().is_empty());
+    assert_eq!(
+      env.step(Action::Place(0)).unwrap_err(),
+      StepError::NoPlacement(0)
+    );
+  }
+
+  #[test]
+  fn the_same_seed_and_actions_play_the_same_episode() {
+    let mut envs = [env(), env()];
+    for env in &mut envs {
+      env.reset(7);
+    }
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for _ in 0..500 {
+      let action = match rng.gen_range(0..4) {
+        0 => Action::Wait,
+        1 => Action::Input(Input::Rotate),
+        _ => Action::Place(rng.gen_range(0..envs[0].placements().len())),
+      };
+      let [first, second] = envs.each_mut().map(|env| env.step(action).unwrap());
+
+      assert_eq!(first.observation, second.observation, "{action:?}");
+      assert_eq!(first.reward, second.reward, "{action:?}");
+      assert_eq!(first.done, second.done, "{action:?}");
+      assert_eq!(first.events, second.events, "{action:?}");
+      if first.done {
+        break;
+      }
+    }
+  }
+}
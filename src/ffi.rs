@@ -0,0 +1,338 @@
+// C API over the engine for programs in other languages. include/rustris.h is generated from
+// this file with cbindgen, so the /// comments here end up in the header.
+//
+// Every function taking a RustrisGame pointer expects one returned by rustris_game_new that has
+// not been freed yet, and does nothing when given null. Buffers must hold at least the length
+// passed with them. A panic in the engine is caught before it reaches C, and the function then
+// returns what it would for a null game.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::board::MAX_WIDTH;
+use crate::game_event::{GameEvent, SpinType};
+use crate::game_state::GameState;
+use crate::input::Input;
+use crate::mode::GameMode;
+use crate::piece::Direction;
+use crate::shape::Color;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+// Pieces spawn in the columns they take on a standard board, which reach column 7 for the I
+const MIN_WIDTH: u8 = 7;
+const MIN_HEIGHT: u8 = 4;
+
+pub const RUSTRIS_INPUT_MOVE_LEFT: u32 = 0;
+pub const RUSTRIS_INPUT_MOVE_RIGHT: u32 = 1;
+pub const RUSTRIS_INPUT_SOFT_DROP: u32 = 2;
+pub const RUSTRIS_INPUT_ROTATE: u32 = 3;
+pub const RUSTRIS_INPUT_HARD_DROP: u32 = 4;
+pub const RUSTRIS_INPUT_HOLD: u32 = 5;
+
+// Board cells and piece colours
+pub const RUSTRIS_COLOR_CYAN: u8 = 0;
+pub const RUSTRIS_COLOR_YELLOW: u8 = 1;
+pub const RUSTRIS_COLOR_PURPLE: u8 = 2;
+pub const RUSTRIS_COLOR_GREEN: u8 = 3;
+pub const RUSTRIS_COLOR_RED: u8 = 4;
+pub const RUSTRIS_COLOR_BLUE: u8 = 5;
+pub const RUSTRIS_COLOR_ORANGE: u8 = 6;
+pub const RUSTRIS_COLOR_GARBAGE: u8 = 7;
+pub const RUSTRIS_COLOR_EMPTY: u8 = 8;
+
+pub const RUSTRIS_EVENT_PIECE_SPAWNED: u32 = 0;
+pub const RUSTRIS_EVENT_MOVED: u32 = 1;
+pub const RUSTRIS_EVENT_ROTATED: u32 = 2;
+pub const RUSTRIS_EVENT_LOCKED: u32 = 3;
+pub const RUSTRIS_EVENT_LINES_CLEARED: u32 = 4;
+pub const RUSTRIS_EVENT_HOLD: u32 = 5;
+pub const RUSTRIS_EVENT_LEVEL_UP: u32 = 6;
+pub const RUSTRIS_EVENT_FINESSE_FAULT: u32 = 7;
+pub const RUSTRIS_EVENT_GAME_OVER: u32 = 8;
+pub const RUSTRIS_EVENT_COMPLETED: u32 = 9;
+
+// Directions of RUSTRIS_EVENT_MOVED
+pub const RUSTRIS_DIRECTION_LEFT: u8 = 0;
+pub const RUSTRIS_DIRECTION_RIGHT: u8 = 1;
+pub const RUSTRIS_DIRECTION_DOWN: u8 = 2;
+
+// Spins of RUSTRIS_EVENT_LINES_CLEARED
+pub const RUSTRIS_SPIN_NONE: u8 = 0;
+pub const RUSTRIS_SPIN_MINI: u8 = 1;
+pub const RUSTRIS_SPIN_FULL: u8 = 2;
+
+/// A game in progress, only handled through pointers. Functions given a null game return 0,
+/// false or nothing, and so do they if the engine fails inside the call.
+pub struct RustrisGame {
+  game_state: GameState,
+  events: VecDeque<GameEvent>,
+}
+
+/// Something that happened in the game. Fields that do not apply to the kind are 0.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RustrisEvent {
+  /// One of RUSTRIS_EVENT_*
+  pub kind: u32,
+  /// RUSTRIS_COLOR_* of the piece that spawned or locked
+  pub color: u8,
+  /// Top-left corner of the locked piece
+  pub x: u8,
+  pub y: u8,
+  /// RUSTRIS_DIRECTION_* of a move
+  pub direction: u8,
+  /// Lines cleared at once
+  pub lines: u8,
+  /// RUSTRIS_SPIN_* of the clear
+  pub spin: u8,
+  /// The level reached
+  pub level: u32,
+  /// Counted key presses for a piece placed with a finesse fault, and the fewest possible
+  pub key_presses: u32,
+  pub optimal: u32,
+}
+
+/// Start a Marathon game. The same seed and inputs on the same frames always play out the same
+/// game. Returns null when the board is not 7 to 64 cells wide and at least 4 tall. Free it
+/// with rustris_game_free.
+#[no_mangle]
+pub extern "C" fn rustris_game_new(width: u8, height: u8, seed: u64) -> *mut RustrisGame {
+  if !(MIN_WIDTH..=MAX_WIDTH).contains(&width) || height < MIN_HEIGHT {
+    return std::ptr::null_mut();
+  }
+
+  catch(std::ptr::null_mut(), || {
+    let game = RustrisGame {
+      game_state: GameState::with_seed(width, height, GameMode::default(), seed),
+      events: VecDeque::new(),
+    };
+    Box::into_raw(Box::new(game))
+  })
+}
+
+/// Free a game from rustris_game_new.
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_free(game: *mut RustrisGame) {
+  if !game.is_null() {
+    catch((), || drop(Box::from_raw(game)));
+  }
+}
+
+/// Press a key, one of RUSTRIS_INPUT_*. Returns false for an unknown input.
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_apply_input(game: *mut RustrisGame, input: u32) -> bool {
+  let Some(game) = game.as_mut() else {
+    return false;
+  };
+  let input = match input {
+    RUSTRIS_INPUT_MOVE_LEFT => Input::MoveLeft,
+    RUSTRIS_INPUT_MOVE_RIGHT => Input::MoveRight,
+    RUSTRIS_INPUT_SOFT_DROP => Input::SoftDrop,
+    RUSTRIS_INPUT_ROTATE => Input::Rotate,
+    RUSTRIS_INPUT_HARD_DROP => Input::HardDrop,
+    RUSTRIS_INPUT_HOLD => Input::Hold,
+    _ => return false,
+  };
+
+  catch(false, || {
+    game.game_state.apply_input(input);
+    true
+  })
+}
+
+/// Advance the game by frames at 60 frames per second: gravity, lock delay and the mode's timers.
+/// Returns false for a null game.
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_step_frames(game: *mut RustrisGame, frames: u32) -> bool {
+  let Some(game) = game.as_mut() else {
+    return false;
+  };
+
+  catch(false, || {
+    for _ in 0..frames {
+      game.game_state.step_frame();
+    }
+    true
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_board_width(game: *const RustrisGame) -> u8 {
+  catch(0, || {
+    game.as_ref().map_or(0, |game| game.game_state.board.width)
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_board_height(game: *const RustrisGame) -> u8 {
+  catch(0, || {
+    game.as_ref().map_or(0, |game| game.game_state.board.height)
+  })
+}
+
+/// Copy the locked cells row by row from the top into buffer as RUSTRIS_COLOR_* values, without
+/// the falling piece. Returns the number of cells on the board, and copies nothing when len is
+/// smaller than that.
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_read_board(
+  game: *const RustrisGame,
+  buffer: *mut u8,
+  len: usize,
+) -> usize {
+  let Some(game) = game.as_ref() else {
+    return 0;
+  };
+  let board = &game.game_state.board;
+  let cells = board.width as usize * board.height as usize;
+  if buffer.is_null() || len < cells {
+    return cells;
+  }
+
+  let buffer = slice::from_raw_parts_mut(buffer, cells);
+  catch(0, || {
+    for y in board.iter_height() {
+      for x in board.iter_width() {
+        buffer[y * board.width as usize + x] = color_code(board.cell(x, y));
+      }
+    }
+    cells
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_score(game: *const RustrisGame) -> u32 {
+  catch(0, || game.as_ref().map_or(0, |game| game.game_state.score))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_lines(game: *const RustrisGame) -> u32 {
+  catch(0, || game.as_ref().map_or(0, |game| game.game_state.lines))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_level(game: *const RustrisGame) -> u32 {
+  catch(0, || game.as_ref().map_or(0, |game| game.game_state.level))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_pieces(game: *const RustrisGame) -> u32 {
+  catch(0, || game.as_ref().map_or(0, |game| game.game_state.pieces))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_is_over(game: *const RustrisGame) -> bool {
+  catch(true, || {
+    game.as_ref().is_none_or(|game| game.game_state.game_over)
+  })
+}
+
+/// Take the oldest event not read yet into event. Returns false when there are none left.
+#[no_mangle]
+pub unsafe extern "C" fn rustris_game_poll_event(
+  game: *mut RustrisGame,
+  event: *mut RustrisEvent,
+) -> bool {
+  let Some(game) = game.as_mut() else {
+    return false;
+  };
+  if event.is_null() {
+    return false;
+  }
+
+  catch(false, || {
+    game.events.extend(game.game_state.drain_events());
+    let Some(next) = game.events.pop_front() else {
+      return false;
+    };
+
+    *event = RustrisEvent::from_event(&next);
+    true
+  })
+}
+
+// Run f, returning fallback instead if it panics, as unwinding into C is undefined behaviour
+fn catch<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+  panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+impl RustrisEvent {
+  fn from_event(event: &GameEvent) -> Self {
+    match *event {
+      GameEvent::PieceSpawned { color } => RustrisEvent {
+        kind: RUSTRIS_EVENT_PIECE_SPAWNED,
+        color: color_code(color),
+        ..Default::default()
+      },
+      GameEvent::Moved { direction } => RustrisEvent {
+        kind: RUSTRIS_EVENT_MOVED,
+        direction: match direction {
+          Direction::Left => RUSTRIS_DIRECTION_LEFT,
+          Direction::Right => RUSTRIS_DIRECTION_RIGHT,
+          Direction::Down => RUSTRIS_DIRECTION_DOWN,
+        },
+        ..Default::default()
+      },
+      GameEvent::Rotated => RustrisEvent {
+        kind: RUSTRIS_EVENT_ROTATED,
+        ..Default::default()
+      },
+      GameEvent::Locked { color, x, y } => RustrisEvent {
+        kind: RUSTRIS_EVENT_LOCKED,
+        color: color_code(color),
+        x,
+        y,
+        ..Default::default()
+      },
+      GameEvent::LinesCleared { ref rows, spin, .. } => RustrisEvent {
+        kind: RUSTRIS_EVENT_LINES_CLEARED,
+        lines: rows.len() as u8,
+        spin: match spin {
+          SpinType::None => RUSTRIS_SPIN_NONE,
+          SpinType::Mini => RUSTRIS_SPIN_MINI,
+          SpinType::Full => RUSTRIS_SPIN_FULL,
+        },
+        ..Default::default()
+      },
+      GameEvent::Hold => RustrisEvent {
+        kind: RUSTRIS_EVENT_HOLD,
+        ..Default::default()
+      },
+      GameEvent::LevelUp { level } => RustrisEvent {
+        kind: RUSTRIS_EVENT_LEVEL_UP,
+        level,
+        ..Default::default()
+      },
+      GameEvent::FinesseFault {
+        key_presses,
+        optimal,
+      } => RustrisEvent {
+        kind: RUSTRIS_EVENT_FINESSE_FAULT,
+        key_presses,
+        optimal,
+        ..Default::default()
+      },
+      GameEvent::GameOver => RustrisEvent {
+        kind: RUSTRIS_EVENT_GAME_OVER,
+        ..Default::default()
+      },
+      GameEvent::Completed => RustrisEvent {
+        kind: RUSTRIS_EVENT_COMPLETED,
+        ..Default::default()
+      },
+    }
+  }
+}
+
+fn color_code(color: Color) -> u8 {
+  match color {
+    Color::Cyan => RUSTRIS_COLOR_CYAN,
+    Color::Yellow => RUSTRIS_COLOR_YELLOW,
+    Color::Purple => RUSTRIS_COLOR_PURPLE,
+    Color::Green => RUSTRIS_COLOR_GREEN,
+    Color::Red => RUSTRIS_COLOR_RED,
+    Color::Blue => RUSTRIS_COLOR_BLUE,
+    Color::Orange => RUSTRIS_COLOR_ORANGE,
+    Color::Garbage => RUSTRIS_COLOR_GARBAGE,
+    Color::None => RUSTRIS_COLOR_EMPTY,
+  }
+}
@@ -0,0 +1,145 @@
+use crate::board::Board;
+use crate::game_event::SpinType;
+use crate::input::Input;
+use crate::movegen::{LockedPlacement, Reach};
+use crate::pathfind::{find_path, KeyPress};
+use crate::piece::Piece;
+
+// Key presses a piece took against the fewest that put it in the same place. Drops are left out
+// of both: soft dropping is free unless a tuck needs it, and every piece ends with a hard drop
+// or a lock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Finesse {
+  pub key_presses: u32,
+  pub optimal: u32,
+}
+
+impl Finesse {
+  // Compare the presses it took to lock the piece against the shortest path from its spawn
+  pub fn judge(
+    spawned: &Piece,
+    board: &Board,
+    locked: &Piece,
+    spin: SpinType,
+    key_presses: u32,
+  ) -> Option<Finesse> {
+    let target = LockedPlacement {
+      piece: locked.clone(),
+      rotation: 0,
+      reach: Reach::Drop,
+      spin,
+    };
+    let path = find_path(spawned, board, &target)?;
+
+    Some(Finesse {
+      key_presses,
+      optimal: path.iter().filter(|&&key_press| counts(key_press)).count() as u32,
+    })
+  }
+
+  pub fn faults(&self) -> u32 {
+    self.key_presses.saturating_sub(self.optimal)
+  }
+}
+
+// Whether a press counts towards finesse
+pub fn counts(key_press: KeyPress) -> bool {
+  match key_press {
+    KeyPress::Tap(input) | KeyPress::Hold(input) => is_counted(input),
+  }
+}
+
+pub fn is_counted(input: Input) -> bool {
+  matches!(input, Input::MoveLeft | Input::MoveRight | Input::Rotate)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shape::PieceKind;
+
+  // The piece dropped to the floor of the empty board in a rotation and column
+  fn dropped(kind: PieceKind, rotation: u8, x: u8, board: &Board) -> Piece {
+    Piece {
+      kind,
+      rotation,
+      x,
+      y: board.height - kind.shape(rotation).height,
+    }
+  }
+
+  fn judge(kind: PieceKind, rotation: u8, x: u8, key_presses: u32) -> Finesse {
+    let board = Board::new(10, 20);
+    let locked = dropped(kind, rotation, x, &board);
+    Finesse::judge(
+      &Piece::new(kind),
+      &board,
+      &locked,
+      SpinType::None,
+      key_presses,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn optimal_paths_have_no_faults() {
+    // Kind, rotation, column, presses on the shortest path
+    let cases = [
+      (PieceKind::T, 0, 4, 0), // Straight down
+      (PieceKind::T, 0, 0, 1), // DAS left
+      (PieceKind::T, 0, 7, 1), // DAS right
+      (PieceKind::T, 0, 3, 1), // One tap left
+      (PieceKind::T, 1, 4, 1), // One rotation
+      (PieceKind::T, 1, 0, 2), // Rotate, DAS left
+      (PieceKind::L, 2, 4, 2), // Two rotations
+      (PieceKind::J, 3, 4, 3), // Three rotations, as there is no counter-clockwise rotation
+      (PieceKind::I, 0, 6, 1), // DAS right
+    ];
+
+    for (kind, rotation, x, optimal) in cases {
+      let finesse = judge(kind, rotation, x, optimal);
+      assert_eq!(
+        finesse.optimal, optimal,
+        "{kind:?} rotation {rotation} at {x}"
+      );
+      assert_eq!(finesse.faults(), 0, "{kind:?} rotation {rotation} at {x}");
+    }
+  }
+
+  #[test]
+  fn extra_presses_are_faults() {
+    // Left then right again
+    assert_eq!(judge(PieceKind::T, 0, 4, 2).faults(), 2);
+    // Tapping twice where DAS takes one press
+    assert_eq!(judge(PieceKind::T, 0, 0, 2).faults(), 1);
+    // Rotating all the way round before rotating once
+    assert_eq!(judge(PieceKind::T, 1, 4, 5).faults(), 4);
+  }
+
+  #[test]
+  fn equivalent_rotations_are_optimal() {
+    let board = Board::new(10, 20);
+
+    // Every O rotation covers the same cells as the spawn orientation
+    for rotation in 0..4 {
+      assert_eq!(
+        judge(PieceKind::O, rotation, 4, 0).faults(),
+        0,
+        "O rotation {rotation}"
+      );
+    }
+
+    for kind in [PieceKind::S, PieceKind::Z, PieceKind::I] {
+      // Flat upside down is the spawn orientation
+      let spawned = Piece::new(kind);
+      assert_eq!(judge(kind, 2, spawned.x, 0).faults(), 0, "{kind:?} flat");
+
+      // Standing on either side is as short, wherever it stands
+      for x in 0..=board.width - kind.shape(1).width {
+        let right = judge(kind, 1, x, 0).optimal;
+        let left = judge(kind, 3, x, 0).optimal;
+        assert_eq!(left, right, "{kind:?} standing at {x}");
+      }
+    }
+  }
+}
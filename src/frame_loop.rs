@@ -0,0 +1,48 @@
+use crate::game_state::FRAMES_PER_SECOND;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+
+// Milliseconds of simulation one frame stands for
+const FRAME_MILLIS: f64 = 1000.0 / FRAMES_PER_SECOND as f64;
+
+// Longest gap between two animation frames that is caught up on, so a tab coming back from the
+// background does not fast forward through everything it missed
+const MAX_CATCH_UP_MILLIS: f64 = 250.0;
+
+type FrameCallback = Closure<dyn FnMut(f64)>;
+
+// Run on_frame FRAMES_PER_SECOND times per second of wall-clock time, for as long as the page
+// lives. Animation frames carry their timestamp, and the time left over after whole frames is
+// kept for the next one, so the game clock does not drift from real time.
+pub fn run(mut on_frame: impl FnMut() + 'static) {
+  let callback: Rc<RefCell<Option<FrameCallback>>> = Rc::new(RefCell::new(None));
+  let next_callback = Rc::clone(&callback);
+  let mut last_time: Option<f64> = None;
+  let mut behind = 0.0;
+
+  *callback.borrow_mut() = Some(Closure::wrap(Box::new(move |time: f64| {
+    if let Some(last_time) = last_time {
+      behind += (time - last_time).clamp(0.0, MAX_CATCH_UP_MILLIS);
+    }
+    last_time = Some(time);
+
+    while behind >= FRAME_MILLIS {
+      behind -= FRAME_MILLIS;
+      on_frame();
+    }
+
+    request_animation_frame(next_callback.borrow().as_ref().unwrap());
+  }) as Box<dyn FnMut(f64)>));
+
+  // The closure holds on to itself through next_callback, which keeps the loop alive
+  request_animation_frame(callback.borrow().as_ref().unwrap());
+}
+
+fn request_animation_frame(callback: &FrameCallback) {
+  web_sys::window()
+    .unwrap()
+    .request_animation_frame(callback.as_ref().unchecked_ref())
+    .expect("Failed to request an animation frame");
+}
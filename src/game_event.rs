@@ -0,0 +1,94 @@
+use crate::piece::Direction;
+use crate::shape::Color;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+  PieceSpawned {
+    color: Color,
+  },
+  Moved {
+    direction: Direction,
+  },
+  Rotated,
+  Locked {
+    color: Color,
+    x: u8,
+    y: u8,
+  },
+  LinesCleared {
+    rows: Vec<u8>,
+    clear_type: ClearType,
+    spin: SpinType,
+  },
+  Hold,
+  LevelUp {
+    level: u32,
+  },
+  FinesseFault {
+    key_presses: u32,
+    optimal: u32,
+  },
+  GameOver,
+  Completed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearType {
+  Single,
+  Double,
+  Triple,
+  Tetris,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpinType {
+  None,
+  Mini,
+  Full,
+}
+
+// Everything about a line clear that attack and bonus rules depend on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClearInfo {
+  pub lines: u8,
+  pub spin: SpinType,
+  pub b2b: u32, // Difficult clears directly before this one, 0 when this is not a back-to-back
+  pub combo: u32, // Line clearing pieces directly before this one
+  pub perfect_clear: bool,
+}
+
+impl ClearInfo {
+  // Tetrises and spins keep a back-to-back chain going
+  pub fn is_difficult(&self) -> bool {
+    self.lines == 4 || (self.lines > 0 && self.spin != SpinType::None)
+  }
+}
+
+impl ClearType {
+  pub fn from_lines(lines: usize) -> Option<ClearType> {
+    match lines {
+      1 => Some(ClearType::Single),
+      2 => Some(ClearType::Double),
+      3 => Some(ClearType::Triple),
+      4 => Some(ClearType::Tetris),
+      _ => None,
+    }
+  }
+}
+
+impl GameEvent {
+  pub fn name(&self) -> &'static str {
+    match self {
+      GameEvent::PieceSpawned { .. } => "PieceSpawned",
+      GameEvent::Moved { .. } => "Moved",
+      GameEvent::Rotated => "Rotated",
+      GameEvent::Locked { .. } => "Locked",
+      GameEvent::LinesCleared { .. } => "LinesCleared",
+      GameEvent::Hold => "Hold",
+      GameEvent::LevelUp { .. } => "LevelUp",
+      GameEvent::FinesseFault { .. } => "FinesseFault",
+      GameEvent::GameOver => "GameOver",
+      GameEvent::Completed => "Completed",
+    }
+  }
+}
@@ -1,4 +1,5 @@
-use crate::game_state::GameState;
+use crate::game_state::{frames_to_millis, GameState};
+use crate::mode::{GameMode, SPLIT_LINES};
 use crate::piece::{Direction, Piece};
 use web_sys::CanvasRenderingContext2d;
 
@@ -13,7 +14,7 @@ impl GameRenderer {
     Self::render_background(canvas, board_width);
 
     // Board
-    Self::render_board_with_pieces(canvas, &game_state);
+    Self::render_board_with_pieces(canvas, game_state);
 
     // Score
     Self::render_score(
@@ -23,15 +24,68 @@ impl GameRenderer {
       (cell_width as f32 * 1.25) as u16,
     );
 
+    // Level and lines, with the lines left until the next level when there is one
+    let lines_text = match game_state.lines_to_next_level() {
+      Some(remaining) => format!("Lines: {} ({})", game_state.lines, remaining),
+      None => format!("Lines: {}", game_state.lines),
+    };
+    Self::render_text(
+      canvas,
+      &format!("Level: {}", game_state.level),
+      board_width + cell_width as u16,
+      (cell_width as f32 * 2.0) as u16,
+    );
+    Self::render_text(
+      canvas,
+      &lines_text,
+      board_width + cell_width as u16,
+      (cell_width as f32 * 2.75) as u16,
+    );
+
+    // Mode name and mode specific details below the hold box
+    Self::render_mode_info(
+      canvas,
+      &Self::mode_info_lines(game_state),
+      board_width + cell_width as u16,
+      (cell_width as f32 * 16.75) as u16,
+    );
+
+    // Final seconds of a timed game, drawn over the board
+    if let GameMode::Ultra(ultra) = &game_state.mode {
+      if ultra.is_warning(game_state) && !game_state.game_over {
+        let seconds_left = frames_to_millis(ultra.frames_left(game_state)).div_ceil(1000);
+        Self::render_time_warning(canvas, seconds_left, board_width);
+      }
+    }
+
+    // Meter along the bottom of the board filling up until the next garbage row rises
+    if let GameMode::Survival(survival) = &game_state.mode {
+      if !game_state.game_over {
+        Self::render_incoming_meter(
+          canvas,
+          survival.next_row_progress(game_state.frame),
+          survival.is_warning(game_state.frame),
+          board_width,
+        );
+      }
+    }
+
+    // Incoming garbage
+    if game_state.garbage.pending_lines() > 0 {
+      Self::render_garbage_meter(canvas, game_state);
+    }
+
     // Held Piece
     Self::render_hold(canvas, &game_state.held_piece, cell_width, board_width);
 
     // Next Piece
     Self::render_next(canvas, &game_state.next_piece, cell_width, board_width);
 
-    // Game Over screen
-    if game_state.game_over {
-      Self::render_game_over(canvas);
+    // Completion or Game Over screen
+    if game_state.completed {
+      Self::render_complete(canvas, game_state);
+    } else if game_state.game_over {
+      Self::render_game_over(canvas, game_state.finesse_faults);
     }
   }
 
@@ -70,7 +124,7 @@ impl GameRenderer {
     // All pieces in grid
     for y in board.iter_height() {
       for x in board.iter_width() {
-        let cell_color = board.grid[y][x].to_rgba(1.0);
+        let cell_color = board.cell(x, y).to_rgba(1.0);
 
         canvas.set_fill_style_str(&cell_color);
         canvas.fill_rect(
@@ -83,7 +137,7 @@ impl GameRenderer {
     }
 
     // The current falling piece
-    let piece_color = piece.shape.color.to_rgba(1.0);
+    let piece_color = piece.shape().color.to_rgba(1.0);
     canvas.set_fill_style_str(&piece_color);
     Self::render_a_piece(canvas, cell_size, piece);
 
@@ -94,18 +148,26 @@ impl GameRenderer {
       ghost_piece.y += 1;
     }
 
-    let ghost_color = ghost_piece.shape.color.to_rgba(0.3);
+    let ghost_color = ghost_piece.shape().color.to_rgba(0.3);
     canvas.set_fill_style_str(&ghost_color);
     Self::render_a_piece(canvas, cell_size, &ghost_piece);
 
+    // Where the finesse trainer wants the piece to go
+    if let GameMode::Finesse(trainer) = &game_state.mode {
+      if let Some(target) = &trainer.target {
+        canvas.set_fill_style_str("rgba(255, 255, 255, 0.25)");
+        Self::render_a_piece(canvas, cell_size, target);
+      }
+    }
+
     // Render gridlines
     Self::render_gridlines(canvas, 0, 0, board.width, board.height, board.cell_size);
   }
 
   fn render_a_piece(canvas: &CanvasRenderingContext2d, cell_size: f64, piece: &Piece) {
-    for y in piece.shape.iter_height() {
-      for x in piece.shape.iter_width() {
-        if piece.shape.cells[y][x] == 1 {
+    for y in piece.shape().iter_height() {
+      for x in piece.shape().iter_width() {
+        if piece.shape().cells[y][x] == 1 {
           canvas.fill_rect(
             (piece.x + x as u8) as f64 * cell_size,
             (piece.y + y as u8) as f64 * cell_size,
@@ -150,6 +212,83 @@ impl GameRenderer {
       .unwrap();
   }
 
+  fn render_text(canvas: &CanvasRenderingContext2d, text: &str, pos_x: u16, pos_y: u16) {
+    canvas.set_fill_style_str("white");
+    canvas.set_font("20px 'Courier New', monospace");
+    canvas.fill_text(text, pos_x as f64, pos_y as f64).unwrap();
+  }
+
+  fn mode_info_lines(game_state: &GameState) -> Vec<String> {
+    let mut lines = vec![if game_state.mode.is_ranked() {
+      game_state.mode.name().to_string()
+    } else {
+      format!("{} (unranked)", game_state.mode.name())
+    }];
+
+    match &game_state.mode {
+      GameMode::Marathon(_) => {}
+      GameMode::Zen(_) => {
+        lines.push(format_time(game_state.elapsed_millis()));
+      }
+      GameMode::Sprint(sprint) => {
+        lines.push(format!(
+          "{} {}/{}",
+          format_time(game_state.elapsed_millis()),
+          game_state.lines.min(sprint.target_lines),
+          sprint.target_lines
+        ));
+
+        for (index, &split_frame) in sprint.splits.iter().enumerate() {
+          lines.push(format!(
+            "{:>3}: {}",
+            (index as u32 + 1) * SPLIT_LINES,
+            format_time(frames_to_millis(split_frame))
+          ));
+        }
+      }
+      GameMode::Dig(dig) => {
+        lines.push(format_time(game_state.elapsed_millis()));
+        lines.push(format!(
+          "Garbage {}/{}",
+          dig.garbage_left(&game_state.board),
+          dig.total_lines
+        ));
+      }
+      GameMode::Survival(survival) => {
+        lines.push(format_time(game_state.elapsed_millis()));
+        if survival.is_warning(game_state.frame) {
+          lines.push("Rows incoming!".to_string());
+        }
+      }
+      GameMode::Ultra(ultra) => {
+        lines.push(format!(
+          "Left {}",
+          format_time(frames_to_millis(ultra.frames_left(game_state)))
+        ));
+      }
+      GameMode::Finesse(trainer) => {
+        lines.push(format!("Target {}/{}", trainer.placed, trainer.targets));
+        lines.push(format!("Correct {}", trainer.correct));
+      }
+    }
+
+    // Every mode keeps count of wasted key presses
+    lines.push(format!("Faults {}", game_state.finesse_faults));
+
+    lines
+  }
+
+  fn render_mode_info(canvas: &CanvasRenderingContext2d, lines: &[String], pos_x: u16, pos_y: u16) {
+    canvas.set_fill_style_str("white");
+    canvas.set_font("18px 'Courier New', monospace");
+
+    for (index, line) in lines.iter().enumerate() {
+      canvas
+        .fill_text(line, pos_x as f64, pos_y as f64 + index as f64 * 22.0)
+        .unwrap();
+    }
+  }
+
   fn render_hold(
     canvas: &CanvasRenderingContext2d,
     held_piece: &Option<Piece>,
@@ -180,15 +319,15 @@ impl GameRenderer {
       .unwrap();
 
     if let Some(ref piece) = held_piece {
-      let piece_width = cell_size as u16 * piece.shape.width as u16;
-      let piece_height = cell_size as u16 * piece.shape.height as u16;
+      let piece_width = cell_size as u16 * piece.shape().width as u16;
+      let piece_height = cell_size as u16 * piece.shape().height as u16;
       let piece_x = box_x + (box_width - piece_width) / 2;
       let piece_y = box_y + (box_height - piece_height) / 2;
-      let color = piece.shape.color.to_rgba(1.0);
+      let color = piece.shape().color.to_rgba(1.0);
 
-      for y in piece.shape.iter_height() {
-        for x in piece.shape.iter_width() {
-          if piece.shape.cells[y][x] == 1 {
+      for y in piece.shape().iter_height() {
+        for x in piece.shape().iter_width() {
+          if piece.shape().cells[y][x] == 1 {
             canvas.set_fill_style_str(&color);
             let cell_x = piece_x + (x as u16 * cell_size as u16);
             let cell_y = piece_y + (y as u16 * cell_size as u16);
@@ -236,15 +375,15 @@ impl GameRenderer {
       )
       .unwrap();
 
-    let piece_width = cell_size as u16 * next_piece.shape.width as u16;
-    let piece_height = cell_size as u16 * next_piece.shape.height as u16;
+    let piece_width = cell_size as u16 * next_piece.shape().width as u16;
+    let piece_height = cell_size as u16 * next_piece.shape().height as u16;
     let piece_x = box_x + (box_width - piece_width) / 2;
     let piece_y = box_y + (box_height - piece_height) / 2;
-    let color = next_piece.shape.color.to_rgba(1.0);
+    let color = next_piece.shape().color.to_rgba(1.0);
 
-    for y in next_piece.shape.iter_height() {
-      for x in next_piece.shape.iter_width() {
-        if next_piece.shape.cells[y][x] == 1 {
+    for y in next_piece.shape().iter_height() {
+      for x in next_piece.shape().iter_width() {
+        if next_piece.shape().cells[y][x] == 1 {
           canvas.set_fill_style_str(&color);
           let cell_x = piece_x + (x as u16 * cell_size as u16);
           let cell_y = piece_y + (y as u16 * cell_size as u16);
@@ -262,7 +401,7 @@ impl GameRenderer {
     }
   }
 
-  fn render_game_over(canvas: &CanvasRenderingContext2d) {
+  fn render_game_over(canvas: &CanvasRenderingContext2d, finesse_faults: u32) {
     canvas.set_fill_style_str("red");
     canvas.set_font("30px Arial");
     canvas
@@ -272,5 +411,138 @@ impl GameRenderer {
         canvas.canvas().unwrap().height() as f64 / 2.0,
       )
       .unwrap();
+
+    canvas.set_fill_style_str("white");
+    canvas.set_font("20px 'Courier New', monospace");
+    canvas
+      .fill_text(
+        &format!("Faults: {}", finesse_faults),
+        (canvas.canvas().unwrap().width() as f64 / 2.0) - 50.0,
+        canvas.canvas().unwrap().height() as f64 / 2.0 + 30.0,
+      )
+      .unwrap();
   }
+
+  fn render_time_warning(canvas: &CanvasRenderingContext2d, seconds_left: u64, board_width: u16) {
+    let center_y = canvas.canvas().unwrap().height() as f64 / 2.0;
+
+    canvas.set_fill_style_str("rgba(255, 0, 0, 0.5)");
+    canvas.set_font("120px Arial");
+    canvas.set_text_align("center");
+    canvas
+      .fill_text(
+        &seconds_left.to_string(),
+        board_width as f64 / 2.0,
+        center_y,
+      )
+      .unwrap();
+    canvas.set_text_align("start");
+  }
+
+  fn render_incoming_meter(
+    canvas: &CanvasRenderingContext2d,
+    progress: f32,
+    warning: bool,
+    board_width: u16,
+  ) {
+    let canvas_height = canvas.canvas().unwrap().height() as f64;
+    let meter_height = 6.0;
+
+    canvas.set_fill_style_str(if warning {
+      "rgba(255, 0, 0, 0.9)"
+    } else {
+      "rgba(255, 255, 255, 0.4)"
+    });
+    canvas.fill_rect(
+      0.0,
+      canvas_height - meter_height,
+      board_width as f64 * progress.clamp(0.0, 1.0) as f64,
+      meter_height,
+    );
+  }
+
+  // Queued garbage as a bar rising along the right edge of the board: red for rows that will
+  // rise on the next lock, orange for rows still on their way
+  fn render_garbage_meter(canvas: &CanvasRenderingContext2d, game_state: &GameState) {
+    let board = &game_state.board;
+    let cell_size = board.cell_size as f64;
+    let board_width = board.width as f64 * cell_size;
+    let board_height = board.height as f64 * cell_size;
+    let meter_width = cell_size / 4.0;
+
+    let pending_lines = game_state.garbage.pending_lines().min(board.height as u32);
+    let arrived_lines = game_state
+      .garbage
+      .arrived_lines(game_state.frame)
+      .min(pending_lines);
+
+    canvas.set_fill_style_str("orange");
+    canvas.fill_rect(
+      board_width,
+      board_height - pending_lines as f64 * cell_size,
+      meter_width,
+      pending_lines as f64 * cell_size,
+    );
+
+    canvas.set_fill_style_str("red");
+    canvas.fill_rect(
+      board_width,
+      board_height - arrived_lines as f64 * cell_size,
+      meter_width,
+      arrived_lines as f64 * cell_size,
+    );
+  }
+
+  pub fn render_banner(canvas: &CanvasRenderingContext2d, text: &str, color: &str) {
+    canvas.set_fill_style_str(color);
+    canvas.set_font("30px Arial");
+    canvas.set_text_align("center");
+    canvas
+      .fill_text(
+        text,
+        canvas.canvas().unwrap().width() as f64 / 2.0,
+        canvas.canvas().unwrap().height() as f64 / 2.0,
+      )
+      .unwrap();
+    canvas.set_text_align("start");
+  }
+
+  fn render_complete(canvas: &CanvasRenderingContext2d, game_state: &GameState) {
+    let center_x = canvas.canvas().unwrap().width() as f64 / 2.0;
+    let center_y = canvas.canvas().unwrap().height() as f64 / 2.0;
+
+    canvas.set_fill_style_str("lime");
+    canvas.set_font("30px Arial");
+    canvas
+      .fill_text("Complete!", center_x - 60.0, center_y)
+      .unwrap();
+
+    canvas.set_fill_style_str("white");
+    canvas.set_font("20px 'Courier New', monospace");
+    // Ultra always runs out its clock, so its result is the score rather than the time
+    let result = match game_state.mode {
+      GameMode::Ultra(_) => format!("Score: {}", game_state.score),
+      _ => format!("Time: {}", format_time(game_state.elapsed_millis())),
+    };
+    canvas
+      .fill_text(&result, center_x - 70.0, center_y + 30.0)
+      .unwrap();
+    canvas
+      .fill_text(
+        &format!("Faults: {}", game_state.finesse_faults),
+        center_x - 70.0,
+        center_y + 55.0,
+      )
+      .unwrap();
+  }
+}
+
+// m:ss.mmm
+pub fn format_time(millis: u64) -> String {
+  format!(
+    "{}:{:02}.{:03}",
+    millis / 60_000,
+    millis / 1000 % 60,
+    millis % 1000
+  )
 }
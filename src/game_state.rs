@@ -1,8 +1,28 @@
+use crate::attack::AttackTable;
 use crate::board::Board;
-use crate::piece::Direction::Down;
+use crate::finesse::{is_counted, Finesse};
+use crate::game_event::{ClearInfo, ClearType, GameEvent, SpinType};
+use crate::garbage::GarbageQueue;
+use crate::input::Input;
+use crate::mode::{GameMode, ZenRecovery};
+use crate::movegen::apply_move;
+use crate::pathfind::KeyPress;
+use crate::piece::Direction::{self, Down, Left, Right};
 use crate::piece::Piece;
+use crate::randomizer::Randomizer;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+use std::collections::VecDeque;
 use std::mem;
 
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+// Frames a landed piece waits before it locks
+const LOCK_DELAY_FRAMES: u32 = 30;
+
+// Pieces known beyond next_piece
+pub const PREVIEW_PIECES: usize = 4;
+
 pub struct GameState {
   pub board: Board,
   pub current_piece: Piece,
@@ -11,15 +31,68 @@ pub struct GameState {
   pub held_piece: Option<Piece>,
   pub hold_used: bool,
   pub next_piece: Piece,
+  pub preview: VecDeque<Piece>, // The pieces after next_piece, in spawn order
+  pub lines: u32,
+  pub pieces: u32,
+  pub level: u32,
+  pub completed: bool,
+  pub mode: GameMode,
+  pub frame: u64,
+  pub seed: u64,
+  pub randomizer: Randomizer, // Draws the preview pieces, left as it was after the last one
+  pub last_clear: Option<ClearInfo>, // Set by every lock that cleared lines, cleared otherwise
+  pub attack_table: AttackTable,
+  pub garbage: GarbageQueue,
+  pub attack_sent: u32,
+  pub finesse_faults: u32,
+  pub last_finesse: Option<Finesse>, // Set by every lock whose path could be judged
+  outgoing_attack: u32,
+  rng: StdRng,
+  rotated_last: bool, // Whether the last successful movement of the piece was a rotation
+  combo_chain: u32,
+  b2b_chain: u32,
+  level_lines: u32,
+  gravity_progress: f32,
+  lock_frames: u32,
+  key_presses: u32, // Counted towards finesse since the current piece came into play
+  spawned_piece: Piece,
+  events: Vec<GameEvent>,
 }
 
 impl GameState {
   pub fn new(board_width: u8, board_height: u8) -> Self {
-    let board = Board::new(board_width, board_height);
-    let current_piece = Piece::random_piece();
-    let next_piece = Piece::random_piece();
+    GameState::with_mode(board_width, board_height, GameMode::default())
+  }
+
+  pub fn with_mode(board_width: u8, board_height: u8, mode: GameMode) -> Self {
+    GameState::with_seed(board_width, board_height, mode, random())
+  }
 
-    GameState {
+  // The same seed, mode and inputs on the same frames always play out the same game
+  pub fn with_seed(board_width: u8, board_height: u8, mode: GameMode, seed: u64) -> Self {
+    GameState::with_randomizer(board_width, board_height, mode, seed, Randomizer::Uniform)
+  }
+
+  pub fn with_randomizer(
+    board_width: u8,
+    board_height: u8,
+    mut mode: GameMode,
+    seed: u64,
+    mut randomizer: Randomizer,
+  ) -> Self {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut board = Board::new(board_width, board_height);
+    mode.on_start(&mut board, &mut rng);
+
+    let current_piece = randomizer.next_piece(&mut rng);
+    let next_piece = randomizer.next_piece(&mut rng);
+    let preview = (0..PREVIEW_PIECES)
+      .map(|_| randomizer.next_piece(&mut rng))
+      .collect();
+    let spawned_color = current_piece.shape().color;
+    let spawned_piece = current_piece.clone();
+
+    let mut game_state = GameState {
       board,
       current_piece,
       game_over: false,
@@ -27,67 +100,525 @@ impl GameState {
       held_piece: None,
       hold_used: false,
       next_piece,
-    }
+      preview,
+      lines: 0,
+      pieces: 0,
+      level: mode.start_level(),
+      completed: false,
+      mode,
+      frame: 0,
+      seed,
+      randomizer,
+      last_clear: None,
+      attack_table: AttackTable::default(),
+      garbage: GarbageQueue::new(seed.rotate_left(32)),
+      attack_sent: 0,
+      finesse_faults: 0,
+      last_finesse: None,
+      outgoing_attack: 0,
+      rng,
+      rotated_last: false,
+      combo_chain: 0,
+      b2b_chain: 0,
+      level_lines: 0,
+      gravity_progress: 0.0,
+      lock_frames: 0,
+      key_presses: 0,
+      spawned_piece,
+      events: vec![GameEvent::PieceSpawned {
+        color: spawned_color,
+      }],
+    };
+
+    game_state.piece_came_into_play();
+    game_state
+  }
+
+  // A game continuing from a position set up elsewhere, like one sent over the bot protocol.
+  // The queue starts with the current piece and needs at least the next piece as well.
+  pub fn from_position(board: Board, queue: &[Piece], held_piece: Option<Piece>) -> Option<Self> {
+    let [current_piece, next_piece, preview @ ..] = queue else {
+      return None;
+    };
+
+    let mut game_state = GameState::with_seed(board.width, board.height, GameMode::default(), 0);
+    game_state.board = board;
+    game_state.current_piece = current_piece.clone();
+    game_state.next_piece = next_piece.clone();
+    game_state.preview = preview.iter().cloned().collect();
+    game_state.held_piece = held_piece;
+    game_state.spawned_piece = game_state.current_piece.clone();
+
+    Some(game_state)
   }
 
-  pub fn state_tick(&mut self) {
+  // Take every event queued since the last call
+  pub fn drain_events(&mut self) -> Vec<GameEvent> {
+    mem::take(&mut self.events)
+  }
+
+  // Advance the simulation by one frame: gravity first, then the lock delay
+  pub fn step_frame(&mut self) {
+    if self.game_over {
+      return;
+    }
+
+    self.frame += 1;
+
+    // Timed modes can end between locks
+    if self.check_completion() {
+      return;
+    }
+
+    let holes = self
+      .mode
+      .on_frame(self.frame, self.board.width, &mut self.rng);
+    if !holes.is_empty() {
+      self.insert_garbage_rows(&holes);
+
+      if self.game_over {
+        return;
+      }
+    }
+
+    if self.mode.has_gravity() {
+      self.gravity_progress += gravity(self.level);
+    }
+    while self.gravity_progress >= 1.0 {
+      self.gravity_progress -= 1.0;
+
+      if self.current_piece.move_piece(Down, &self.board) {
+        self.rotated_last = false;
+      } else {
+        self.gravity_progress = 0.0;
+      }
+    }
+
     if self.current_piece.can_move(Down, &self.board) {
-      self.current_piece.move_piece(Down, &self.board);
+      self.lock_frames = 0;
     } else {
-      self.merge_current_piece();
-      self.spawn_new_piece();
+      self.lock_frames += 1;
+
+      if self.lock_frames >= LOCK_DELAY_FRAMES {
+        self.lock_piece();
+      }
     }
   }
 
-  pub fn merge_current_piece(&mut self) {
-    for y in self.current_piece.shape.iter_height() {
-      for x in self.current_piece.shape.iter_width() {
-        if self.current_piece.shape.cells[y][x] == 1 {
-          let board_y = self.current_piece.y as usize + y;
-          let board_x = self.current_piece.x as usize + x;
+  // Milliseconds of play so far, measured on the simulation clock
+  pub fn elapsed_millis(&self) -> u64 {
+    frames_to_millis(self.frame)
+  }
 
-          self.board.grid[board_y][board_x] = self.current_piece.shape.color;
+  // A key press from the player
+  pub fn apply_input(&mut self, input: Input) {
+    if is_counted(input) && !self.game_over {
+      self.key_presses += 1;
+    }
+
+    self.apply_repeated_input(input);
+  }
+
+  // An input from a key that is still held down, which finesse does not count as a new press
+  pub fn apply_repeated_input(&mut self, input: Input) {
+    if self.game_over {
+      return;
+    }
+
+    match input {
+      Input::MoveLeft => self.move_piece(Left),
+      Input::MoveRight => self.move_piece(Right),
+      Input::SoftDrop => self.move_piece(Down),
+      Input::Rotate => self.rotate_piece(),
+      Input::HardDrop => self.hard_drop(),
+      Input::Hold => self.hold_piece(),
+    }
+  }
+
+  // Holding a key repeats its input for as long as the piece keeps moving
+  pub fn apply_key_press(&mut self, key_press: KeyPress) {
+    match key_press {
+      KeyPress::Tap(input) => self.apply_input(input),
+      KeyPress::Hold(input) => {
+        self.apply_input(input);
+        while !self.game_over && apply_move(&mut self.current_piece.clone(), input, &self.board) {
+          self.apply_repeated_input(input);
         }
       }
     }
+  }
+
+  pub fn move_piece(&mut self, direction: Direction) {
+    if self.current_piece.move_piece(direction, &self.board) {
+      self.rotated_last = false;
+      self.events.push(GameEvent::Moved { direction });
+    }
+  }
+
+  pub fn rotate_piece(&mut self) {
+    if self.current_piece.rotate_piece(&self.board) {
+      self.rotated_last = true;
+      self.events.push(GameEvent::Rotated);
+    }
+  }
+
+  pub fn hard_drop(&mut self) {
+    while self.current_piece.can_move(Down, &self.board) {
+      self.current_piece.move_piece(Down, &self.board);
+      self.rotated_last = false;
+    }
+
+    self.lock_piece();
+  }
+
+  fn lock_piece(&mut self) {
+    self.lock_frames = 0;
+    self.gravity_progress = 0.0;
+    self.merge_current_piece();
+    self.settle_garbage();
+    if self.game_over {
+      return;
+    }
+    // The locked piece is part of the board now, so only blocks pushed off the top end the game
+    let holes = self.mode.on_piece_locked(&mut self.board, &mut self.rng);
+    if holes
+      .into_iter()
+      .any(|hole| self.board.push_garbage_row(hole))
+    {
+      self.top_out();
+      return;
+    }
+
+    if !self.check_completion() {
+      self.spawn_new_piece();
+    }
+  }
+
+  // A clear first cancels queued garbage and sends the rest out, any other lock raises the
+  // garbage that has arrived
+  fn settle_garbage(&mut self) {
+    match self.last_clear {
+      Some(clear) => {
+        let attack = self.attack_table.attack(&clear);
+        let attack = self.garbage.cancel(attack);
+
+        self.outgoing_attack += attack;
+        self.attack_sent += attack;
+      }
+      None => {
+        let holes = self.garbage.take_arrived(self.frame, self.board.width);
+        self.insert_garbage_rows(&holes);
+      }
+    }
+  }
 
-    let lines_cleared = self.board.clear_full_lines();
-    self.score += match lines_cleared {
-      1 => 100,
-      2 => 300,
-      3 => 500,
-      4 => 800,
-      _ => 0,
+  // Queue garbage sent by an opponent
+  pub fn receive_garbage(&mut self, lines: u32) {
+    self.garbage.push(lines, self.frame, self.board.width);
+  }
+
+  // Line clearing pieces in a row so far
+  pub fn combo(&self) -> u32 {
+    self.combo_chain
+  }
+
+  pub fn is_back_to_back(&self) -> bool {
+    self.b2b_chain > 0
+  }
+
+  // Attack produced since the last call, after cancelling against this board's own queue
+  pub fn take_outgoing_attack(&mut self) -> u32 {
+    mem::take(&mut self.outgoing_attack)
+  }
+
+  // End the game when the mode's goal has been reached
+  fn check_completion(&mut self) -> bool {
+    if self.mode.is_complete(self) {
+      self.game_over = true;
+      self.completed = true;
+      self.events.push(GameEvent::Completed);
+    }
+
+    self.completed
+  }
+
+  pub fn merge_current_piece(&mut self) {
+    let spin = if self.rotated_last {
+      self.current_piece.t_spin(&self.board)
+    } else {
+      SpinType::None
     };
 
+    self.judge_finesse(spin);
+    self.board.merge_piece(&self.current_piece);
+
+    self.pieces += 1;
+    self.events.push(GameEvent::Locked {
+      color: self.current_piece.shape().color,
+      x: self.current_piece.x,
+      y: self.current_piece.y,
+    });
+
+    let cleared_rows = self.board.clear_full_lines();
+    self.score += self.level
+      * match cleared_rows.len() {
+        1 => 100,
+        2 => 300,
+        3 => 500,
+        4 => 800,
+        _ => 0,
+      };
+
+    self.last_clear = self.clear_info(cleared_rows.len() as u8, spin);
+
+    if let Some(clear_type) = ClearType::from_lines(cleared_rows.len()) {
+      self.add_lines(cleared_rows.len() as u32);
+      self.events.push(GameEvent::LinesCleared {
+        rows: cleared_rows,
+        clear_type,
+        spin,
+      });
+    }
+
+    self.rotated_last = false;
     self.hold_used = false;
   }
 
+  fn judge_finesse(&mut self, spin: SpinType) {
+    self.last_finesse = Finesse::judge(
+      &self.spawned_piece,
+      &self.board,
+      &self.current_piece,
+      spin,
+      self.key_presses,
+    );
+    self
+      .mode
+      .on_piece_placed(&self.current_piece, self.last_finesse);
+
+    if let Some(finesse) = self.last_finesse.filter(|finesse| finesse.faults() > 0) {
+      self.finesse_faults += finesse.faults();
+      self.events.push(GameEvent::FinesseFault {
+        key_presses: finesse.key_presses,
+        optimal: finesse.optimal,
+      });
+    }
+  }
+
+  // Advance the combo and back-to-back chains for a lock, describing the clear if there was one
+  fn clear_info(&mut self, lines: u8, spin: SpinType) -> Option<ClearInfo> {
+    if lines == 0 {
+      self.combo_chain = 0;
+      return None;
+    }
+
+    let mut info = ClearInfo {
+      lines,
+      spin,
+      b2b: 0,
+      combo: self.combo_chain,
+      perfect_clear: self.board.is_empty(),
+    };
+    self.combo_chain += 1;
+
+    if info.is_difficult() {
+      info.b2b = self.b2b_chain;
+      self.b2b_chain += 1;
+    } else {
+      self.b2b_chain = 0;
+    }
+
+    Some(info)
+  }
+
+  fn add_lines(&mut self, lines: u32) {
+    self.lines += lines;
+    self.level_lines += lines;
+    self.mode.on_lines_cleared(self.lines, self.frame);
+
+    while let Some(goal) = self.mode.level_goal(self.level) {
+      if self.level_lines < goal {
+        break;
+      }
+
+      self.level_lines -= goal;
+      self.level += 1;
+      self.events.push(GameEvent::LevelUp { level: self.level });
+    }
+  }
+
+  // Lines still needed to reach the next level, None on the last level
+  pub fn lines_to_next_level(&self) -> Option<u32> {
+    self
+      .mode
+      .level_goal(self.level)
+      .map(|goal| goal.saturating_sub(self.level_lines))
+  }
+
+  // Push garbage rows in under the stack, one per hole column. The falling piece keeps its
+  // place unless the rising stack reaches it, in which case it is lifted along.
+  pub fn insert_garbage_rows(&mut self, holes: &[usize]) {
+    for &hole in holes {
+      if self.board.push_garbage_row(hole) {
+        self.top_out();
+        return;
+      }
+
+      if !self.current_piece.can_stay(&self.board) {
+        if self.current_piece.y == 0 {
+          self.top_out();
+          return;
+        }
+
+        self.current_piece.y -= 1;
+      }
+    }
+  }
+
+  // Modes without top-out make room for the next piece instead
+  fn recover_blocked_spawn(&mut self, piece: &Piece) {
+    match self.mode.top_out_recovery() {
+      Some(ZenRecovery::ClearTopRows) => {
+        for row in self.board.iter_height() {
+          if piece.can_stay(&self.board) {
+            break;
+          }
+
+          self.board.clear_row(row);
+        }
+      }
+      Some(ZenRecovery::ClearBoard) => self.board.clear(),
+      None => {}
+    }
+  }
+
+  fn top_out(&mut self) {
+    self.game_over = true;
+    self.events.push(GameEvent::GameOver);
+  }
+
   pub fn spawn_new_piece(&mut self) {
     if !self.next_piece.can_stay(&self.board) {
-      self.game_over = true;
+      self.recover_blocked_spawn(&self.next_piece.clone());
+    }
+
+    if !self.next_piece.can_stay(&self.board) {
+      self.top_out();
     } else {
-      self.current_piece = self.next_piece.clone();
-      self.next_piece = Piece::random_piece();
+      self
+        .preview
+        .push_back(self.randomizer.next_piece(&mut self.rng));
+      let next_piece = self.preview.pop_front().unwrap();
+      self.current_piece = mem::replace(&mut self.next_piece, next_piece);
+      self.events.push(GameEvent::PieceSpawned {
+        color: self.current_piece.shape().color,
+      });
+      self.piece_came_into_play();
     }
   }
 
+  // Start judging the new current piece from where it is now
+  fn piece_came_into_play(&mut self) {
+    self.key_presses = 0;
+    self.spawned_piece = self.current_piece.clone();
+    self
+      .mode
+      .on_piece_spawned(&self.current_piece, &self.board, &mut self.rng);
+  }
+
   pub fn hold_piece(&mut self) {
-    if self.hold_used {
+    if self.hold_used || self.game_over {
       return;
     }
 
+    // The hold comes before the spawn it causes, which may top out
+    self.hold_used = true;
+    self.events.push(GameEvent::Hold);
+
     if let Some(mut held_piece) = self.held_piece.take() {
       mem::swap(&mut self.current_piece, &mut held_piece);
-      self.current_piece.x = self.board.width / 2 - self.current_piece.shape.width / 2;
+      self.current_piece.x = self.board.width / 2 - self.current_piece.shape().width / 2;
       self.current_piece.y = 0;
-
       self.held_piece = Some(held_piece);
+
+      if !self.current_piece.can_stay(&self.board) {
+        self.recover_blocked_spawn(&self.current_piece.clone());
+      }
+      if !self.current_piece.can_stay(&self.board) {
+        self.top_out();
+        return;
+      }
+
+      self.events.push(GameEvent::PieceSpawned {
+        color: self.current_piece.shape().color,
+      });
+      self.piece_came_into_play();
     } else {
       self.held_piece = Some(self.current_piece.clone());
       self.spawn_new_piece();
     }
+  }
+}
 
-    self.hold_used = true;
+// Rows per frame for a level, following the guideline speed curve up to level 20
+fn gravity(level: u32) -> f32 {
+  let level = level.clamp(1, 20) as f32;
+  let seconds_per_row = (0.8 - (level - 1.0) * 0.007).powf(level - 1.0);
+
+  1.0 / (seconds_per_row * FRAMES_PER_SECOND as f32)
+}
+
+pub fn frames_to_millis(frames: u64) -> u64 {
+  frames * 1000 / FRAMES_PER_SECOND as u64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shape::Color;
+
+  fn game() -> GameState {
+    let mut game_state = GameState::with_seed(10, 20, GameMode::default(), 0);
+    game_state.drain_events();
+    game_state
+  }
+
+  #[test]
+  fn hold_comes_before_the_spawn_it_causes() {
+    let mut game_state = game();
+    let next = game_state.next_piece.shape().color;
+    game_state.hold_piece();
+    assert_eq!(
+      game_state.drain_events(),
+      [GameEvent::Hold, GameEvent::PieceSpawned { color: next }]
+    );
+
+    game_state.hard_drop();
+    game_state.drain_events();
+    let held = game_state.held_piece.as_ref().unwrap().shape().color;
+    game_state.hold_piece();
+    assert_eq!(
+      game_state.drain_events(),
+      [GameEvent::Hold, GameEvent::PieceSpawned { color: held }]
+    );
+  }
+
+  #[test]
+  fn no_events_after_a_hold_tops_out() {
+    let mut game_state = game();
+    for y in 0..4 {
+      for x in game_state.board.iter_width() {
+        game_state.board.set_cell(x, y, Color::Garbage);
+      }
+    }
+
+    game_state.hold_piece();
+    assert_eq!(
+      game_state.drain_events(),
+      [GameEvent::Hold, GameEvent::GameOver]
+    );
+    game_state.hold_piece();
+    game_state.apply_input(Input::Hold);
+    assert!(game_state.drain_events().is_empty());
   }
 }
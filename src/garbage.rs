@@ -0,0 +1,190 @@
+use crate::game_state::FRAMES_PER_SECOND;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+// Picks hole columns for consecutive garbage rows. Messiness is the chance that a row's hole
+// moves away from the previous row's column: 0.0 gives one clean well, 1.0 a new column every row.
+#[derive(Clone)]
+pub struct GarbageHoles {
+  pub messiness: f32,
+  hole: Option<usize>,
+}
+
+impl GarbageHoles {
+  pub fn new(messiness: f32) -> Self {
+    GarbageHoles {
+      messiness: messiness.clamp(0.0, 1.0),
+      hole: None,
+    }
+  }
+
+  // Continue from a known hole column
+  pub fn starting_at(hole: usize, messiness: f32) -> Self {
+    GarbageHoles {
+      messiness: messiness.clamp(0.0, 1.0),
+      hole: Some(hole),
+    }
+  }
+
+  pub fn next_hole(&mut self, rng: &mut impl Rng, width: usize) -> usize {
+    let hole = match self.hole {
+      Some(hole) if width > 1 && rng.gen::<f32>() < self.messiness => {
+        // Move to any other column
+        (hole + rng.gen_range(1..width)) % width
+      }
+      Some(hole) => hole,
+      None => rng.gen_range(0..width),
+    };
+
+    self.hole = Some(hole);
+    hole
+  }
+}
+
+pub struct GarbageEntry {
+  pub lines: u32,
+  pub arrival_frame: u64, // First frame on which the entry may rise
+  pub hole: usize,
+}
+
+// Garbage received but not yet raised onto the board. Entries wait out a delay, can be
+// cancelled by the receiver's own attacks, and rise when a piece locks without clearing lines.
+pub struct GarbageQueue {
+  pub delay_frames: u64,
+  pub messiness: f32, // Chance per row that the hole moves while an entry rises
+  entries: VecDeque<GarbageEntry>,
+  rng: StdRng, // Kept apart from the piece sequence so garbage never changes which pieces come
+}
+
+impl GarbageQueue {
+  pub const DEFAULT_DELAY_FRAMES: u64 = FRAMES_PER_SECOND as u64;
+
+  pub fn new(seed: u64) -> Self {
+    GarbageQueue {
+      delay_frames: GarbageQueue::DEFAULT_DELAY_FRAMES,
+      messiness: 0.0,
+      entries: VecDeque::new(),
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+
+  pub fn push(&mut self, lines: u32, frame: u64, board_width: u8) {
+    if lines == 0 {
+      return;
+    }
+
+    let hole = self.rng.gen_range(0..board_width as usize);
+    self.entries.push_back(GarbageEntry {
+      lines,
+      arrival_frame: frame + self.delay_frames,
+      hole,
+    });
+  }
+
+  // Use outgoing attack to cancel queued garbage, oldest first, and return what is left over
+  pub fn cancel(&mut self, mut attack: u32) -> u32 {
+    while attack > 0 {
+      let Some(entry) = self.entries.front_mut() else {
+        break;
+      };
+
+      let cancelled = attack.min(entry.lines);
+      entry.lines -= cancelled;
+      attack -= cancelled;
+
+      if entry.lines == 0 {
+        self.entries.pop_front();
+      }
+    }
+
+    attack
+  }
+
+  pub fn pending_lines(&self) -> u32 {
+    self.entries.iter().map(|entry| entry.lines).sum()
+  }
+
+  pub fn arrived_lines(&self, frame: u64) -> u32 {
+    self
+      .entries
+      .iter()
+      .filter(|entry| entry.arrival_frame <= frame)
+      .map(|entry| entry.lines)
+      .sum()
+  }
+
+  // Remove every arrived entry and return the hole column of each row to raise
+  pub fn take_arrived(&mut self, frame: u64, board_width: u8) -> Vec<usize> {
+    let mut holes = Vec::new();
+
+    while self
+      .entries
+      .front()
+      .is_some_and(|entry| entry.arrival_frame <= frame)
+    {
+      let entry = self.entries.pop_front().unwrap();
+      let mut entry_holes = GarbageHoles::starting_at(entry.hole, self.messiness);
+
+      holes.push(entry.hole);
+      for _ in 1..entry.lines {
+        holes.push(entry_holes.next_hole(&mut self.rng, board_width as usize));
+      }
+    }
+
+    holes
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn queue(delay_frames: u64, messiness: f32) -> GarbageQueue {
+    GarbageQueue {
+      delay_frames,
+      messiness,
+      ..GarbageQueue::new(0)
+    }
+  }
+
+  #[test]
+  fn cancel_takes_from_the_front_first() {
+    let mut garbage = queue(0, 0.0);
+    garbage.push(3, 0, 10);
+    garbage.push(2, 0, 10);
+
+    assert_eq!(garbage.cancel(2), 0);
+    assert_eq!(garbage.pending_lines(), 3);
+    assert_eq!(garbage.take_arrived(0, 10).len(), 3);
+
+    garbage.push(1, 0, 10);
+    assert_eq!(garbage.cancel(4), 3);
+    assert_eq!(garbage.pending_lines(), 0);
+  }
+
+  #[test]
+  fn arrives_after_the_delay() {
+    let mut garbage = queue(60, 0.0);
+    garbage.push(2, 100, 10);
+
+    assert_eq!(garbage.arrived_lines(159), 0);
+    assert!(garbage.take_arrived(159, 10).is_empty());
+    assert_eq!(garbage.arrived_lines(160), 2);
+    assert_eq!(garbage.take_arrived(160, 10).len(), 2);
+    assert_eq!(garbage.pending_lines(), 0);
+  }
+
+  #[test]
+  fn messiness_moves_the_hole() {
+    let mut clean = queue(0, 0.0);
+    clean.push(8, 0, 10);
+    let holes = clean.take_arrived(0, 10);
+    assert!(holes.iter().all(|&hole| hole == holes[0]));
+
+    let mut messy = queue(0, 1.0);
+    messy.push(8, 0, 10);
+    let holes = messy.take_arrived(0, 10);
+    assert!(holes.windows(2).all(|pair| pair[0] != pair[1]));
+  }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+  MoveLeft,
+  MoveRight,
+  SoftDrop,
+  Rotate,
+  HardDrop,
+  Hold,
+}
+
+// Where an instance listens for keyboard events
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputScope {
+  Canvas, // Only while the instance's canvas has focus
+  Window, // Page-wide, routed through the instance's own key bindings
+}
+
+#[derive(Clone)]
+pub struct KeyBindings {
+  bindings: HashMap<String, Input>,
+}
+
+impl KeyBindings {
+  pub fn empty() -> Self {
+    KeyBindings {
+      bindings: HashMap::new(),
+    }
+  }
+
+  pub fn bind(&mut self, key: &str, input: Input) {
+    self.bindings.insert(normalize_key(key), input);
+  }
+
+  pub fn unbind(&mut self, key: &str) {
+    self.bindings.remove(&normalize_key(key));
+  }
+
+  pub fn clear(&mut self) {
+    self.bindings.clear();
+  }
+
+  pub fn input_for(&self, key: &str) -> Option<Input> {
+    self.bindings.get(&normalize_key(key)).copied()
+  }
+
+  // Left side of a shared keyboard
+  pub fn player_one() -> Self {
+    let mut key_bindings = KeyBindings::empty();
+    key_bindings.bind("a", Input::MoveLeft);
+    key_bindings.bind("d", Input::MoveRight);
+    key_bindings.bind("w", Input::Rotate);
+    key_bindings.bind("s", Input::SoftDrop);
+    key_bindings.bind(" ", Input::HardDrop);
+    key_bindings.bind("q", Input::Hold);
+    key_bindings
+  }
+
+  // Right side of a shared keyboard
+  pub fn player_two() -> Self {
+    let mut key_bindings = KeyBindings::empty();
+    key_bindings.bind("ArrowLeft", Input::MoveLeft);
+    key_bindings.bind("ArrowRight", Input::MoveRight);
+    key_bindings.bind("ArrowUp", Input::Rotate);
+    key_bindings.bind("ArrowDown", Input::SoftDrop);
+    key_bindings.bind("Enter", Input::HardDrop);
+    key_bindings.bind("Shift", Input::Hold);
+    key_bindings
+  }
+}
+
+impl Default for KeyBindings {
+  fn default() -> Self {
+    let mut key_bindings = KeyBindings::empty();
+    key_bindings.bind("ArrowLeft", Input::MoveLeft);
+    key_bindings.bind("ArrowRight", Input::MoveRight);
+    key_bindings.bind("ArrowUp", Input::Rotate);
+    key_bindings.bind("ArrowDown", Input::SoftDrop);
+    key_bindings.bind(" ", Input::HardDrop);
+    key_bindings.bind("Shift", Input::Hold);
+    key_bindings
+  }
+}
+
+// Letters match whatever the case, so bindings keep working while Shift or Caps Lock is held.
+// Named keys such as "ArrowLeft" are left as they are.
+fn normalize_key(key: &str) -> String {
+  if key.chars().count() == 1 {
+    key.to_lowercase()
+  } else {
+    key.to_string()
+  }
+}
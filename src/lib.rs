@@ -1,160 +1,691 @@
-mod board;
+pub mod attack;
+pub mod board;
+pub mod bot;
+pub mod env;
+pub mod ffi;
+pub mod finesse;
+mod frame_loop;
+pub mod game_event;
 mod game_renderer;
-mod game_state;
-mod piece;
-mod shape;
+pub mod game_state;
+pub mod garbage;
+pub mod input;
+pub mod mode;
+pub mod movegen;
+pub mod pathfind;
+pub mod perfect_clear;
+pub mod piece;
+pub mod randomizer;
+pub mod shape;
+mod snapshot;
+pub mod tbp;
+mod tetris_tbp;
+mod tetris_versus;
+pub mod versus;
 
+use crate::bot::{BeamSearchBot, Bot, HeuristicBot, Weights, DEFAULT_MOVE_FRAMES};
 use crate::game_renderer::GameRenderer;
 use crate::game_state::GameState;
-use crate::piece::Direction::{Down, Left, Right};
+use crate::input::{Input, InputScope, KeyBindings};
+use crate::mode::{
+  Dig, FinesseTrainer, GameMode, LevelGoal, Marathon, Sprint, Survival, Ultra, Zen, ZenRecovery,
+};
+use crate::movegen::{covers_same_cells, locked_placements};
+use crate::pathfind::{find_path, path_inputs};
+use crate::perfect_clear::{pc_chance, solve, PcChanceError, MAX_UNKNOWN_PIECES};
+use crate::piece::Piece;
+use crate::randomizer::Randomizer;
+use crate::shape::{Color, PieceKind};
+use crate::snapshot::{dispatch_events, PcStepView, PieceView};
+use crate::tbp::BotMessage;
+pub use crate::tetris_tbp::TetrisTbpBot;
+pub use crate::tetris_versus::TetrisVersus;
+use rand::random;
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, Document, HtmlCanvasElement, KeyboardEvent, Window};
+use web_sys::js_sys::Function;
+use web_sys::{
+  CanvasRenderingContext2d, Document, EventTarget, HtmlCanvasElement, KeyboardEvent, Window,
+};
 
+// The JS handle only holds a shared reference, so the game can still be driven and inspected
+// from JS after start_game has handed it to the timer and key listeners.
 #[wasm_bindgen]
 pub struct Tetris {
+  game: Rc<RefCell<Game>>,
+}
+
+struct Game {
+  canvas: HtmlCanvasElement,
   ctx: CanvasRenderingContext2d,
   game_state: GameState,
+  mode: GameMode,         // The mode as the game started, to restart it from a seed
+  randomizer: Randomizer, // How new games draw their pieces
+  key_bindings: KeyBindings,
+  input_scope: InputScope,
+  event_listeners: Vec<Function>,
+  started: bool,
+  bot: Bot,
+  autoplay: bool,
+  bot_move_frames: u64,
 }
 
 #[wasm_bindgen]
 impl Tetris {
   #[wasm_bindgen(constructor)]
   pub fn new(canvas_id: &str) -> Result<Tetris, JsValue> {
-    let window: Window = web_sys::window().unwrap();
-    let document: Document = window.document().unwrap();
-    let canvas = document
-      .get_element_by_id(canvas_id)
-      .ok_or("Canvas not found")?
-      .dyn_into::<HtmlCanvasElement>()?;
-    let ctx = canvas
-      .get_context("2d")?
-      .ok_or("Failed to get canvas context")?
-      .dyn_into::<CanvasRenderingContext2d>()?;
-
     let game_state = GameState::new(10, 20);
+    let (canvas, ctx) = board_canvas(canvas_id, &game_state)?;
+
+    // Make the canvas focusable so it can receive its own keyboard events
+    canvas.set_tab_index(0);
+
+    let game = Game {
+      canvas,
+      ctx,
+      game_state,
+      mode: GameMode::default(),
+      randomizer: Randomizer::Uniform,
+      key_bindings: KeyBindings::default(),
+      input_scope: InputScope::Canvas,
+      event_listeners: Vec::new(),
+      started: false,
+      bot: Bot::default(),
+      autoplay: false,
+      bot_move_frames: DEFAULT_MOVE_FRAMES,
+    };
 
-    canvas.set_width((game_state.board.width + 7) as u32 * game_state.board.cell_size as u32);
-    canvas.set_height((game_state.board.height) as u32 * game_state.board.cell_size as u32);
+    Ok(Tetris {
+      game: Rc::new(RefCell::new(game)),
+    })
+  }
 
-    Ok(Tetris { ctx, game_state })
+  // Choose whether keys are read while the canvas has focus or page-wide.
+  // Must be called before start_game.
+  pub fn set_input_scope(&self, input_scope: InputScope) {
+    self.game.borrow_mut().input_scope = input_scope;
   }
 
-  pub fn start_game(self) {
-    let game_ref = Rc::new(RefCell::new(self));
+  pub fn bind_key(&self, key: &str, input: Input) {
+    self.game.borrow_mut().key_bindings.bind(key, input);
+  }
 
-    let tick_game_clone = Rc::clone(&game_ref);
-    let tick_closure = Closure::wrap(Box::new(move || {
-      tick_game_clone.borrow_mut().game_tick();
-    }) as Box<dyn FnMut()>);
+  pub fn unbind_key(&self, key: &str) {
+    self.game.borrow_mut().key_bindings.unbind(key);
+  }
 
-    let window = web_sys::window().unwrap();
-    window
-      .set_interval_with_callback_and_timeout_and_arguments_0(
-        tick_closure.as_ref().unchecked_ref(),
-        500,
-      )
-      .expect("Failed to set interval");
+  pub fn clear_key_bindings(&self) {
+    self.game.borrow_mut().key_bindings.clear();
+  }
+
+  // Restart the game as a Marathon from start_level. Without a final level it runs until
+  // top-out; variable_goal asks five lines per level number instead of ten per level.
+  pub fn set_marathon_mode(&self, start_level: u32, final_level: Option<u32>, variable_goal: bool) {
+    let goal = if variable_goal {
+      LevelGoal::Variable
+    } else {
+      LevelGoal::Fixed(10)
+    };
+
+    let marathon = Marathon::new(start_level, final_level, goal);
+    Self::update(&self.game, |game| {
+      game.new_game(GameMode::Marathon(marathon));
+    });
+  }
+
+  // Restart the game as a Sprint that ends once target_lines are cleared, 40 by default
+  pub fn set_sprint_mode(&self, target_lines: Option<u32>) {
+    let sprint = Sprint::new(target_lines.unwrap_or(Sprint::DEFAULT_TARGET_LINES));
+    Self::update(&self.game, |game| game.new_game(GameMode::Sprint(sprint)));
+  }
+
+  // Restart the game as Ultra: score as much as possible before the time limit, 2 minutes by
+  // default
+  pub fn set_ultra_mode(&self, time_limit_seconds: Option<u32>) {
+    let ultra = Ultra::new(time_limit_seconds.unwrap_or(Ultra::DEFAULT_TIME_LIMIT_SECONDS));
+    Self::update(&self.game, |game| game.new_game(GameMode::Ultra(ultra)));
+  }
+
+  // Restart the game as finesse practice: place targets pieces on their marked spots with as
+  // few key presses as possible, 20 by default
+  pub fn set_finesse_mode(&self, targets: Option<u32>) {
+    let trainer = FinesseTrainer::new(targets.unwrap_or(FinesseTrainer::DEFAULT_TARGETS));
+    Self::update(&self.game, |game| game.new_game(GameMode::Finesse(trainer)));
+  }
+
+  // Restart the game as a Dig race: clear total_lines of garbage, of which visible_height rows
+  // are on the board at a time. Messiness from 0 to 1 is how often the hole changes column.
+  pub fn set_dig_mode(
+    &self,
+    total_lines: Option<u32>,
+    visible_height: Option<u8>,
+    messiness: Option<f32>,
+  ) {
+    let dig = Dig::new(
+      total_lines.unwrap_or(Dig::DEFAULT_TOTAL_LINES),
+      visible_height.unwrap_or(Dig::DEFAULT_VISIBLE_HEIGHT),
+      messiness.unwrap_or(0.5),
+    );
+    Self::update(&self.game, |game| game.new_game(GameMode::Dig(dig)));
+  }
+
+  // Restart the game as Survival: garbage rows keep rising from the bottom, starting every
+  // start_interval_seconds and coming faster over time
+  pub fn set_survival_mode(&self, start_interval_seconds: Option<f32>, messiness: Option<f32>) {
+    let survival = Survival::new(
+      start_interval_seconds.unwrap_or(Survival::DEFAULT_START_INTERVAL_SECONDS),
+      messiness.unwrap_or(0.3),
+    );
+    Self::update(&self.game, |game| {
+      game.new_game(GameMode::Survival(survival))
+    });
+  }
+
+  // Let the bot play the current piece every few frames, e.g. as an attract mode
+  pub fn set_autoplay(&self, enabled: bool, use_hold: bool) {
+    let mut game = self.game.borrow_mut();
+    game.autoplay = enabled;
+    game.bot.set_use_hold(use_hold);
+  }
+
+  pub fn is_autoplay(&self) -> bool {
+    self.game.borrow().autoplay
+  }
+
+  // Feature weights the bot scores placements with, negative values penalise a feature
+  pub fn set_bot_weights(
+    &self,
+    aggregate_height: f32,
+    lines_cleared: f32,
+    holes: f32,
+    bumpiness: f32,
+    wells: f32,
+  ) {
+    self.game.borrow_mut().bot.set_weights(Weights {
+      aggregate_height,
+      lines_cleared,
+      holes,
+      bumpiness,
+      wells,
+    });
+  }
+
+  // Feature weights from JSON, like the file tune_weights writes
+  pub fn load_bot_weights(&self, json: &str) -> Result<(), JsValue> {
+    let weights: Weights =
+      serde_json::from_str(json).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    self.game.borrow_mut().bot.set_weights(weights);
+    Ok(())
+  }
+
+  // Switch to the greedy bot that only looks at the current and hold piece
+  pub fn set_heuristic_bot(&self) {
+    let mut game = self.game.borrow_mut();
+    game.bot = Bot::Heuristic(HeuristicBot {
+      weights: game.bot.weights(),
+      use_hold: true,
+    });
+  }
+
+  // Switch to the bot that searches through the preview, keeping `width` boards per piece for
+  // `depth` pieces. It settles for the best move so far after max_nodes placements or the time
+  // budget.
+  pub fn set_beam_search_bot(
+    &self,
+    width: usize,
+    depth: usize,
+    max_nodes: usize,
+    time_budget_millis: Option<f64>,
+  ) {
+    let mut game = self.game.borrow_mut();
+    game.bot = Bot::BeamSearch(BeamSearchBot {
+      weights: game.bot.weights(),
+      use_hold: true,
+      width,
+      depth,
+      max_nodes,
+      time_budget_millis,
+    });
+  }
+
+  // The Tetris Bot Protocol start message for the current game, to hand control to an outside
+  // bot such as one running in a web worker
+  pub fn tbp_start_message(&self) -> String {
+    serde_json::to_string(&tbp::start_message(&self.game.borrow().game_state)).unwrap()
+  }
+
+  // Play the first move of a bot's suggestion message. Returns the messages to send back to
+  // the bot, or an error when the move cannot be played on our rules.
+  pub fn tbp_play_suggestion(&self, suggestion: &str) -> Result<Vec<String>, JsValue> {
+    let tbp_move = match serde_json::from_str(suggestion) {
+      Ok(BotMessage::Suggestion { moves }) => moves.first().copied(),
+      _ => None,
+    }
+    .ok_or_else(|| JsValue::from_str("Not a suggestion with a move"))?;
+
+    let messages = Self::update(&self.game, |game| {
+      let messages = tbp::play_move(&mut game.game_state, &tbp_move);
+      game.render();
+      messages
+    })
+    .map_err(|reason| JsValue::from_str(&reason))?;
+
+    Ok(
+      messages
+        .iter()
+        .map(|message| serde_json::to_string(message).unwrap())
+        .collect(),
+    )
+  }
+
+  // The inputs along the shortest path that locks the current piece at x and y after the given
+  // clockwise rotations, e.g. to animate how to get there. None when it cannot get there.
+  pub fn path_to(&self, x: u8, y: u8, rotations: u8) -> Option<Vec<Input>> {
+    let game = self.game.borrow();
+    let game_state = &game.game_state;
+    let piece = &game_state.current_piece;
+
+    let wanted = Piece {
+      rotation: (piece.rotation + rotations) % 4,
+      x,
+      y,
+      ..piece.clone()
+    };
+
+    let target = locked_placements(piece, &game_state.board)
+      .into_iter()
+      .find(|placement| covers_same_cells(&placement.piece, &wanted))?;
+    let path = find_path(piece, &game_state.board, &target)?;
+    Some(path_inputs(piece, &game_state.board, &path))
+  }
+
+  // Placements that perfect clear the bottom height rows with the current, next and preview
+  // pieces and the hold, in the order to play them, each saying whether to hold first. None when
+  // the queue has no perfect clear.
+  pub fn perfect_clear(&self, height: u8) -> Option<Vec<PcStepView>> {
+    let game = self.game.borrow();
+    let (queue, hold) = pc_queue(&game.game_state);
+
+    let solution = solve(&game.game_state.board, &queue, hold, height, 1).pop()?;
+    Some(solution.iter().map(PcStepView::from_step).collect())
+  }
+
+  // The chance of a perfect clear of the bottom height rows, with the pieces after the preview
+  // drawn the way this game draws them. 0 when the stack does not fit that height, and an error
+  // when the clear needs more pieces past the preview than can be looked ahead.
+  pub fn perfect_clear_chance(&self, height: u8) -> Result<f64, JsValue> {
+    let game = self.game.borrow();
+    let game_state = &game.game_state;
+    let (queue, hold) = pc_queue(game_state);
+
+    match pc_chance(
+      &game_state.board,
+      &queue,
+      hold,
+      &game_state.randomizer,
+      height,
+    ) {
+      Ok(chance) => Ok(chance.probability()),
+      Err(PcChanceError::NoClear) => Ok(0.0),
+      Err(PcChanceError::TooManyUnknown(unknown)) => Err(JsValue::from_str(&format!(
+        "{} pieces past the preview are needed, at most {} can be looked ahead",
+        unknown, MAX_UNKNOWN_PIECES
+      ))),
+    }
+  }
+
+  // The key presses the bot would play for the current piece
+  pub fn bot_inputs(&self) -> Vec<Input> {
+    let game = self.game.borrow();
+    game.bot.inputs(&game.game_state)
+  }
+
+  // Frames between two bot placements, 1 plays as fast as the game ticks
+  pub fn set_bot_speed(&self, move_frames: u64) {
+    self.game.borrow_mut().bot_move_frames = move_frames.max(1);
+  }
 
-    // Prevent the closure from being dropped
-    tick_closure.forget();
+  // Restart the game in Zen: no top-out and optionally no gravity. A blocked spawn clears the
+  // top rows, or the whole board when clear_board is set.
+  pub fn set_zen_mode(&self, gravity: bool, clear_board: bool) {
+    let recovery = if clear_board {
+      ZenRecovery::ClearBoard
+    } else {
+      ZenRecovery::ClearTopRows
+    };
 
-    Self::attach_input_listeners(game_ref);
+    let zen = Zen::new(gravity, recovery);
+    Self::update(&self.game, |game| game.new_game(GameMode::Zen(zen)));
   }
 
-  fn attach_input_listeners(game_ref: Rc<RefCell<Tetris>>) {
+  // Register a callback that receives every game event as a plain object with a `type` field
+  pub fn on_event(&self, callback: Function) {
+    self.game.borrow_mut().event_listeners.push(callback);
+  }
+
+  pub fn clear_event_listeners(&self) {
+    self.game.borrow_mut().event_listeners.clear();
+  }
+
+  pub fn start_game(&self) {
+    if self.game.borrow().started {
+      return;
+    }
+    self.game.borrow_mut().started = true;
+
+    let tick_game_clone = Rc::clone(&self.game);
+    frame_loop::run(move || Self::update(&tick_game_clone, Game::game_tick));
+
+    Self::attach_input_listeners(Rc::clone(&self.game));
+  }
+
+  fn attach_input_listeners(game_ref: Rc<RefCell<Game>>) {
+    let target: EventTarget = {
+      let game = game_ref.borrow();
+      match game.input_scope {
+        InputScope::Canvas => {
+          // Take keyboard focus only when nothing else has it, so the first board started gets it
+          // and later ones wait for a click
+          let document = web_sys::window().unwrap().document().unwrap();
+          let focused = document.active_element();
+          if focused.is_none_or(|element| element.tag_name() == "BODY") {
+            game.canvas.focus().ok();
+          }
+          game.canvas.clone().into()
+        }
+        InputScope::Window => web_sys::window().unwrap().into(),
+      }
+    };
+
     let game_clone = Rc::clone(&game_ref);
     let closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
-      let mut game = game_clone.borrow_mut();
-      match event.key().as_str() {
-        "ArrowLeft" => game.move_left(),
-        "ArrowRight" => game.move_right(),
-        "ArrowUp" => game.rotate_piece(),
-        "ArrowDown" => game.move_down(),
-        " " => game.hard_drop(),
-        "Shift" => game.hold_piece(),
-        _ => {}
+      let input = game_clone.borrow().key_bindings.input_for(&event.key());
+      if let Some(input) = input {
+        // Keep bound keys (arrows, space) from scrolling the page
+        event.prevent_default();
+        let repeat = event.repeat();
+        Self::update(&game_clone, |game| game.handle_input(input, repeat));
       }
     }) as Box<dyn FnMut(_)>);
 
-    web_sys::window()
-      .unwrap()
+    target
       .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
       .expect("Failed to add keydown listener");
 
     closure.forget();
   }
 
-  // Game tick: Advance the block down one cell and redraw
-  fn game_tick(&mut self) {
-    if !self.game_state.game_over {
-      self.game_state.state_tick();
-    }
+  // Run an action on the game, then deliver the events it queued once the game is no longer
+  // borrowed, so callbacks are free to call back into this instance
+  fn update<R>(game_ref: &Rc<RefCell<Game>>, action: impl FnOnce(&mut Game) -> R) -> R {
+    let (result, events, listeners) = {
+      let mut game = game_ref.borrow_mut();
+      let result = action(&mut game);
+      (
+        result,
+        game.game_state.drain_events(),
+        game.event_listeners.clone(),
+      )
+    };
 
-    self.render();
+    dispatch_events(&events, &listeners);
+    result
   }
 
-  // Move the falling block left
-  pub fn move_left(&mut self) {
-    self
-      .game_state
-      .current_piece
-      .move_piece(Left, &self.game_state.board);
+  // Advance the game by one frame, for harnesses that drive the game without start_game
+  pub fn tick(&self) {
+    Self::update(&self.game, Game::game_tick);
+  }
 
-    self.render();
+  pub fn apply_input(&self, input: Input) {
+    Self::update(&self.game, |game| game.handle_input(input, false));
+  }
+
+  // Move the falling block left
+  pub fn move_left(&self) {
+    self.apply_input(Input::MoveLeft);
   }
 
   // Move the falling block right
-  pub fn move_right(&mut self) {
-    self
-      .game_state
-      .current_piece
-      .move_piece(Right, &self.game_state.board);
+  pub fn move_right(&self) {
+    self.apply_input(Input::MoveRight);
+  }
 
-    self.render();
+  pub fn move_down(&self) {
+    self.apply_input(Input::SoftDrop);
+  }
+
+  pub fn rotate_piece(&self) {
+    self.apply_input(Input::Rotate);
+  }
+
+  pub fn hold_piece(&self) {
+    self.apply_input(Input::Hold);
+  }
+
+  pub fn hard_drop(&self) {
+    self.apply_input(Input::HardDrop);
+  }
+
+  pub fn score(&self) -> u32 {
+    self.game.borrow().game_state.score
+  }
+
+  // Key presses wasted over the game compared to the fewest that reach each placement
+  pub fn finesse_faults(&self) -> u32 {
+    self.game.borrow().game_state.finesse_faults
+  }
+
+  pub fn is_game_over(&self) -> bool {
+    self.game.borrow().game_state.game_over
+  }
+
+  // Whether the game ended by reaching the mode's goal rather than topping out
+  pub fn is_complete(&self) -> bool {
+    self.game.borrow().game_state.completed
+  }
+
+  // Whether the current mode's scores count for rankings, false in Zen
+  pub fn is_ranked(&self) -> bool {
+    self.game.borrow().game_state.mode.is_ranked()
+  }
+
+  pub fn level(&self) -> u32 {
+    self.game.borrow().game_state.level
   }
 
-  fn move_down(&mut self) {
-    self
+  pub fn lines(&self) -> u32 {
+    self.game.borrow().game_state.lines
+  }
+
+  pub fn frame(&self) -> u64 {
+    self.game.borrow().game_state.frame
+  }
+
+  // Seed of the piece sequence, enough together with the inputs and their frames to replay a game
+  // with new_game_with_seed
+  pub fn seed(&self) -> u64 {
+    self.game.borrow().game_state.seed
+  }
+
+  // Restart the current mode, drawing pieces from shuffled bags of all seven when seven_bag is
+  // set and every piece at random otherwise
+  pub fn set_seven_bag(&self, seven_bag: bool) {
+    Self::update(&self.game, |game| {
+      game.randomizer = if seven_bag {
+        Randomizer::seven_bag()
+      } else {
+        Randomizer::Uniform
+      };
+      let mode = game.mode.clone();
+      game.new_game(mode)
+    });
+  }
+
+  // Restart the current mode with the piece sequence of seed
+  pub fn new_game_with_seed(&self, seed: u64) {
+    Self::update(&self.game, |game| {
+      let mode = game.mode.clone();
+      game.new_game_from(mode, seed)
+    });
+  }
+
+  pub fn elapsed_millis(&self) -> u64 {
+    self.game.borrow().game_state.elapsed_millis()
+  }
+
+  // Frames at which each 10 line split of a Sprint was reached, empty in other modes
+  pub fn sprint_splits(&self) -> Vec<u64> {
+    match &self.game.borrow().game_state.mode {
+      GameMode::Sprint(sprint) => sprint.splits.clone(),
+      _ => Vec::new(),
+    }
+  }
+
+  pub fn board_width(&self) -> u8 {
+    self.game.borrow().game_state.board.width
+  }
+
+  pub fn board_height(&self) -> u8 {
+    self.game.borrow().game_state.board.height
+  }
+
+  // Locked cells row by row as Color values, without the falling piece
+  pub fn board_cells(&self) -> Vec<Color> {
+    let board = &self.game.borrow().game_state.board;
+    board
+      .iter_height()
+      .flat_map(|y| board.iter_width().map(move |x| board.cell(x, y)))
+      .collect()
+  }
+
+  pub fn current_piece(&self) -> PieceView {
+    PieceView::from_piece(&self.game.borrow().game_state.current_piece)
+  }
+
+  pub fn next_piece(&self) -> PieceView {
+    PieceView::from_piece(&self.game.borrow().game_state.next_piece)
+  }
+
+  // The pieces coming after next_piece, in order
+  pub fn preview_pieces(&self) -> Vec<PieceView> {
+    let game = self.game.borrow();
+    game
       .game_state
-      .current_piece
-      .move_piece(Down, &self.game_state.board);
+      .preview
+      .iter()
+      .map(PieceView::from_piece)
+      .collect()
+  }
 
-    self.render();
+  pub fn held_piece(&self) -> Option<PieceView> {
+    let game = self.game.borrow();
+    game
+      .game_state
+      .held_piece
+      .as_ref()
+      .map(PieceView::from_piece)
   }
 
-  fn hold_piece(&mut self) {
-    self.game_state.hold_piece();
+  // Queue garbage lines as if an opponent had sent them
+  pub fn receive_garbage(&self, lines: u32) {
+    Self::update(&self.game, |game| {
+      game.game_state.receive_garbage(lines);
+      game.render();
+    });
+  }
+
+  pub fn pending_garbage(&self) -> u32 {
+    self.game.borrow().game_state.garbage.pending_lines()
+  }
+
+  // Attack produced since the last call, for relaying to another instance
+  pub fn take_outgoing_attack(&self) -> u32 {
+    self.game.borrow_mut().game_state.take_outgoing_attack()
+  }
+
+  pub fn is_hold_used(&self) -> bool {
+    self.game.borrow().game_state.hold_used
+  }
+}
+
+// The piece kinds a perfect clear can be played with: current, next and preview in order, and the
+// held piece
+fn pc_queue(game_state: &GameState) -> (Vec<PieceKind>, Option<PieceKind>) {
+  let queue = [&game_state.current_piece, &game_state.next_piece]
+    .into_iter()
+    .chain(&game_state.preview)
+    .map(|piece| piece.kind)
+    .collect();
+  let hold = game_state.held_piece.as_ref().map(|piece| piece.kind);
+
+  (queue, hold)
+}
+
+// Look up a canvas by id and size it for a board plus its side panel
+fn board_canvas(
+  canvas_id: &str,
+  game_state: &GameState,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+  let window: Window = web_sys::window().unwrap();
+  let document: Document = window.document().unwrap();
+  let canvas = document
+    .get_element_by_id(canvas_id)
+    .ok_or("Canvas not found")?
+    .dyn_into::<HtmlCanvasElement>()?;
+  let ctx = canvas
+    .get_context("2d")?
+    .ok_or("Failed to get canvas context")?
+    .dyn_into::<CanvasRenderingContext2d>()?;
+
+  canvas.set_width((game_state.board.width + 7) as u32 * game_state.board.cell_size as u32);
+  canvas.set_height((game_state.board.height) as u32 * game_state.board.cell_size as u32);
+
+  Ok((canvas, ctx))
+}
+
+impl Game {
+  // Key repeats from a held key are not new presses as far as finesse is concerned
+  fn handle_input(&mut self, input: Input, repeat: bool) {
+    if repeat {
+      self.game_state.apply_repeated_input(input);
+    } else {
+      self.game_state.apply_input(input);
+    }
     self.render();
   }
 
-  fn rotate_piece(&mut self) {
-    self
-      .game_state
-      .current_piece
-      .rotate_piece(&self.game_state.board);
+  fn new_game(&mut self, mode: GameMode) {
+    self.new_game_from(mode, random());
+  }
 
+  fn new_game_from(&mut self, mode: GameMode, seed: u64) {
+    let board = &self.game_state.board;
+    self.game_state = GameState::with_randomizer(
+      board.width,
+      board.height,
+      mode.clone(),
+      seed,
+      self.randomizer.clone(),
+    );
+    self.mode = mode;
     self.render();
   }
 
-  pub fn hard_drop(&mut self) {
-    while self
-      .game_state
-      .current_piece
-      .can_move(Down, &self.game_state.board)
+  // Game tick: Advance the simulation one frame and redraw
+  fn game_tick(&mut self) {
+    self.game_state.step_frame();
+
+    if self.autoplay
+      && !self.game_state.game_over
+      && self.game_state.frame.is_multiple_of(self.bot_move_frames)
     {
-      self
-        .game_state
-        .current_piece
-        .move_piece(Down, &self.game_state.board);
+      self.bot.play(&mut self.game_state);
     }
 
-    self.game_state.merge_current_piece();
-    self.game_state.spawn_new_piece();
-
     self.render();
   }
 
@@ -0,0 +1,159 @@
+mod dig;
+mod finesse;
+mod marathon;
+mod sprint;
+mod survival;
+mod ultra;
+mod zen;
+
+pub use dig::Dig;
+pub use finesse::FinesseTrainer;
+pub use marathon::{LevelGoal, Marathon};
+pub use sprint::{Sprint, SPLIT_LINES};
+pub use survival::Survival;
+pub use ultra::Ultra;
+pub use zen::{Zen, ZenRecovery};
+
+use crate::board::Board;
+use crate::finesse::Finesse;
+use crate::game_state::GameState;
+use crate::piece::Piece;
+use rand::Rng;
+
+// The rules that differ between modes: where levels start, how they advance and when the game
+// is won. Topping out ends every mode.
+#[derive(Clone)]
+pub enum GameMode {
+  Marathon(Marathon),
+  Sprint(Sprint),
+  Ultra(Ultra),
+  Dig(Dig),
+  Survival(Survival),
+  Zen(Zen),
+  Finesse(FinesseTrainer),
+}
+
+impl GameMode {
+  pub fn name(&self) -> &'static str {
+    match self {
+      GameMode::Marathon(_) => "Marathon",
+      GameMode::Sprint(_) => "Sprint",
+      GameMode::Ultra(_) => "Ultra",
+      GameMode::Dig(_) => "Dig",
+      GameMode::Survival(_) => "Survival",
+      GameMode::Zen(_) => "Zen",
+      GameMode::Finesse(_) => "Finesse",
+    }
+  }
+
+  pub fn start_level(&self) -> u32 {
+    match self {
+      GameMode::Marathon(marathon) => marathon.start_level,
+      GameMode::Sprint(_)
+      | GameMode::Ultra(_)
+      | GameMode::Dig(_)
+      | GameMode::Survival(_)
+      | GameMode::Zen(_)
+      | GameMode::Finesse(_) => 1,
+    }
+  }
+
+  // Lines needed to leave the given level, None when the level is the last one
+  pub fn level_goal(&self, level: u32) -> Option<u32> {
+    match self {
+      GameMode::Marathon(marathon) => marathon.level_goal(level),
+      GameMode::Sprint(_)
+      | GameMode::Ultra(_)
+      | GameMode::Dig(_)
+      | GameMode::Survival(_)
+      | GameMode::Zen(_)
+      | GameMode::Finesse(_) => None,
+    }
+  }
+
+  pub fn is_complete(&self, game_state: &GameState) -> bool {
+    match self {
+      GameMode::Marathon(marathon) => marathon.is_complete(game_state),
+      GameMode::Sprint(sprint) => sprint.is_complete(game_state),
+      GameMode::Ultra(ultra) => ultra.is_complete(game_state),
+      GameMode::Dig(dig) => dig.is_complete(game_state),
+      GameMode::Finesse(trainer) => trainer.is_complete(),
+      GameMode::Survival(_) | GameMode::Zen(_) => false,
+    }
+  }
+
+  // Whether scores from this mode belong on a leaderboard
+  pub fn is_ranked(&self) -> bool {
+    !matches!(self, GameMode::Zen(_) | GameMode::Finesse(_))
+  }
+
+  pub fn has_gravity(&self) -> bool {
+    match self {
+      GameMode::Zen(zen) => zen.gravity,
+      GameMode::Finesse(_) => false,
+      _ => true,
+    }
+  }
+
+  // How a blocked spawn is resolved instead of ending the game, None means it tops out
+  pub fn top_out_recovery(&self) -> Option<ZenRecovery> {
+    match self {
+      GameMode::Zen(zen) => Some(zen.recovery),
+      _ => None,
+    }
+  }
+
+  // Prepare the board before the first piece spawns
+  pub fn on_start(&mut self, board: &mut Board, rng: &mut impl Rng) {
+    if let GameMode::Dig(dig) = self {
+      dig.on_start(board, rng);
+    }
+  }
+
+  // Called when a piece comes into play, after spawning or from the hold
+  pub fn on_piece_spawned(&mut self, piece: &Piece, board: &Board, rng: &mut impl Rng) {
+    if let GameMode::Finesse(trainer) = self {
+      trainer.pick_target(piece, board, rng);
+    }
+  }
+
+  // Called with a piece's finesse just before it locks
+  pub fn on_piece_placed(&mut self, piece: &Piece, finesse: Option<Finesse>) {
+    if let GameMode::Finesse(trainer) = self {
+      trainer.on_piece_placed(piece, finesse);
+    }
+  }
+
+  // Called after a piece has locked and full lines were cleared. Returns hole columns of garbage
+  // rows to raise under the board.
+  pub fn on_piece_locked(&mut self, board: &mut Board, rng: &mut impl Rng) -> Vec<usize> {
+    match self {
+      GameMode::Dig(dig) => dig.refill(board, rng),
+      GameMode::Finesse(_) => {
+        board.clear();
+        Vec::new()
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  // Hole columns of garbage rows that rise from the bottom on this frame
+  pub fn on_frame(&mut self, frame: u64, board_width: u8, rng: &mut impl Rng) -> Vec<usize> {
+    match self {
+      GameMode::Survival(survival) => survival.on_frame(frame, board_width as usize, rng),
+      _ => Vec::new(),
+    }
+  }
+
+  pub fn on_lines_cleared(&mut self, total_lines: u32, frame: u64) {
+    if let GameMode::Sprint(sprint) = self {
+      sprint.on_lines_cleared(total_lines, frame);
+    }
+  }
+}
+
+impl Default for GameMode {
+  fn default() -> Self {
+    GameMode::Marathon(Marathon::endless())
+  }
+}
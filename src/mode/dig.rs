@@ -0,0 +1,93 @@
+use crate::board::Board;
+use crate::game_state::GameState;
+use crate::garbage::GarbageHoles;
+use rand::Rng;
+
+// Empty rows always left above the garbage so pieces can spawn
+const SPAWN_ROWS: u8 = 4;
+
+#[derive(Clone)]
+pub struct Dig {
+  pub total_lines: u32,
+  pub visible_height: u8,
+  pub holes: GarbageHoles,
+  queued_lines: u32, // Garbage not yet pushed onto the board
+}
+
+impl Dig {
+  pub const DEFAULT_TOTAL_LINES: u32 = 18;
+  pub const DEFAULT_VISIBLE_HEIGHT: u8 = 10;
+
+  pub fn new(total_lines: u32, visible_height: u8, messiness: f32) -> Self {
+    Dig {
+      total_lines: total_lines.max(1),
+      visible_height: visible_height.max(1),
+      holes: GarbageHoles::new(messiness),
+      queued_lines: total_lines.max(1),
+    }
+  }
+
+  pub fn on_start(&mut self, board: &mut Board, rng: &mut impl Rng) {
+    let max_height = board.height.saturating_sub(SPAWN_ROWS).max(1);
+    self.visible_height = self.visible_height.min(max_height);
+    for hole in self.refill(board, rng) {
+      board.push_garbage_row(hole);
+    }
+  }
+
+  // Hole columns of the garbage rows that top the board back up to its visible height while there
+  // is more to dig
+  pub fn refill(&mut self, board: &Board, rng: &mut impl Rng) -> Vec<usize> {
+    let missing = (self.visible_height as usize).saturating_sub(board.garbage_row_count());
+    let rows = missing.min(self.queued_lines as usize);
+    self.queued_lines -= rows as u32;
+
+    (0..rows)
+      .map(|_| self.holes.next_hole(rng, board.width as usize))
+      .collect()
+  }
+
+  pub fn garbage_left(&self, board: &Board) -> u32 {
+    self.queued_lines + board.garbage_row_count() as u32
+  }
+
+  pub fn is_complete(&self, game_state: &GameState) -> bool {
+    self.garbage_left(&game_state.board) == 0
+  }
+}
+
+impl Default for Dig {
+  fn default() -> Self {
+    Dig::new(Dig::DEFAULT_TOTAL_LINES, Dig::DEFAULT_VISIBLE_HEIGHT, 0.5)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::game_event::GameEvent;
+  use crate::mode::GameMode;
+  use crate::shape::Color;
+
+  fn dig_game(height: u8) -> GameState {
+    GameState::with_seed(10, height, GameMode::Dig(Dig::new(30, 10, 0.0)), 0)
+  }
+
+  #[test]
+  fn refill_tops_out_when_the_stack_reaches_the_top() {
+    let mut game_state = dig_game(20);
+    game_state.board.clear_row(19);
+    game_state.board.set_cell(0, 0, Color::Cyan);
+    game_state.drain_events();
+
+    game_state.hard_drop();
+    assert!(game_state.game_over);
+    assert_eq!(game_state.drain_events().last(), Some(&GameEvent::GameOver));
+  }
+
+  #[test]
+  fn short_boards_keep_a_garbage_row() {
+    let game_state = dig_game(3);
+    assert_eq!(game_state.board.garbage_row_count(), 1);
+  }
+}
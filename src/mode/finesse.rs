@@ -0,0 +1,64 @@
+use crate::board::Board;
+use crate::finesse::Finesse;
+use crate::movegen::{covers_same_cells, locked_placements, Reach};
+use crate::piece::Piece;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// Finesse practice on an empty board. Every piece gets a target spot and counts as correct when
+// it locks there with no more key presses than needed.
+#[derive(Clone)]
+pub struct FinesseTrainer {
+  pub targets: u32,
+  pub target: Option<Piece>,
+  pub placed: u32,
+  pub correct: u32,
+}
+
+impl FinesseTrainer {
+  pub const DEFAULT_TARGETS: u32 = 20;
+
+  pub fn new(targets: u32) -> Self {
+    FinesseTrainer {
+      targets,
+      target: None,
+      placed: 0,
+      correct: 0,
+    }
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.placed >= self.targets
+  }
+
+  // Any spot the piece can be dropped into straight from above
+  pub fn pick_target(&mut self, piece: &Piece, board: &Board, rng: &mut impl Rng) {
+    let drops: Vec<Piece> = locked_placements(piece, board)
+      .into_iter()
+      .filter(|placement| placement.reach == Reach::Drop)
+      .map(|placement| placement.piece)
+      .collect();
+
+    self.target = drops.choose(rng).cloned();
+  }
+
+  pub fn on_piece_placed(&mut self, piece: &Piece, finesse: Option<Finesse>) {
+    let on_target = self
+      .target
+      .as_ref()
+      .is_some_and(|target| covers_same_cells(target, piece));
+    let clean = finesse.is_some_and(|finesse| finesse.faults() == 0);
+
+    self.placed += 1;
+    if on_target && clean {
+      self.correct += 1;
+    }
+    self.target = None;
+  }
+}
+
+impl Default for FinesseTrainer {
+  fn default() -> Self {
+    FinesseTrainer::new(FinesseTrainer::DEFAULT_TARGETS)
+  }
+}
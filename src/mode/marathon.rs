@@ -0,0 +1,59 @@
+use crate::game_state::GameState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelGoal {
+  Fixed(u32), // The same number of lines for every level
+  Variable,   // Five lines per level number, so level 3 takes 15 lines
+}
+
+#[derive(Clone)]
+pub struct Marathon {
+  pub start_level: u32,
+  pub final_level: Option<u32>, // None plays until top-out
+  pub goal: LevelGoal,
+}
+
+impl Marathon {
+  pub fn new(start_level: u32, final_level: Option<u32>, goal: LevelGoal) -> Self {
+    let start_level = start_level.max(1);
+
+    Marathon {
+      start_level,
+      final_level: final_level.map(|level| level.max(start_level)),
+      goal,
+    }
+  }
+
+  pub fn endless() -> Self {
+    Marathon::new(1, None, LevelGoal::Fixed(10))
+  }
+
+  pub fn level_goal(&self, level: u32) -> Option<u32> {
+    if self.final_level == Some(level) {
+      return None;
+    }
+
+    Some(self.lines_for_level(level))
+  }
+
+  // Won once every level up to and including the final one has been cleared
+  pub fn is_complete(&self, game_state: &GameState) -> bool {
+    match self.final_level {
+      Some(final_level) => {
+        let total_lines: u32 = (self.start_level..=final_level)
+          .map(|level| self.lines_for_level(level))
+          .sum();
+
+        game_state.lines >= total_lines
+      }
+      None => false,
+    }
+  }
+
+  fn lines_for_level(&self, level: u32) -> u32 {
+    match self.goal {
+      LevelGoal::Fixed(lines) => lines.max(1),
+      LevelGoal::Variable => 5 * level,
+    }
+  }
+}
@@ -0,0 +1,39 @@
+use crate::game_state::GameState;
+
+// Lines between recorded split times
+pub const SPLIT_LINES: u32 = 10;
+
+#[derive(Clone)]
+pub struct Sprint {
+  pub target_lines: u32,
+  pub splits: Vec<u64>, // Frame at which each block of SPLIT_LINES was finished
+}
+
+impl Sprint {
+  pub const DEFAULT_TARGET_LINES: u32 = 40;
+
+  pub fn new(target_lines: u32) -> Self {
+    Sprint {
+      target_lines: target_lines.max(1),
+      splits: Vec::new(),
+    }
+  }
+
+  pub fn on_lines_cleared(&mut self, total_lines: u32, frame: u64) {
+    let counted_lines = total_lines.min(self.target_lines);
+
+    while (self.splits.len() as u32 + 1) * SPLIT_LINES <= counted_lines {
+      self.splits.push(frame);
+    }
+  }
+
+  pub fn is_complete(&self, game_state: &GameState) -> bool {
+    game_state.lines >= self.target_lines
+  }
+}
+
+impl Default for Sprint {
+  fn default() -> Self {
+    Sprint::new(Sprint::DEFAULT_TARGET_LINES)
+  }
+}
@@ -0,0 +1,66 @@
+use crate::game_state::FRAMES_PER_SECOND;
+use crate::garbage::GarbageHoles;
+use rand::Rng;
+
+// How far ahead of a rising row the incoming warning shows
+const WARNING_FRAMES: u64 = 2 * FRAMES_PER_SECOND as u64;
+
+#[derive(Clone)]
+pub struct Survival {
+  pub min_interval_frames: u64,
+  pub speedup: f32, // Each new interval is the previous one times this factor
+  pub holes: GarbageHoles,
+  interval_frames: u64,
+  next_row_frame: u64,
+}
+
+impl Survival {
+  pub const DEFAULT_START_INTERVAL_SECONDS: f32 = 8.0;
+
+  pub fn new(start_interval_seconds: f32, messiness: f32) -> Self {
+    let start_interval_frames =
+      ((start_interval_seconds * FRAMES_PER_SECOND as f32) as u64).max(FRAMES_PER_SECOND as u64);
+
+    Survival {
+      min_interval_frames: FRAMES_PER_SECOND as u64,
+      speedup: 0.95,
+      holes: GarbageHoles::new(messiness),
+      interval_frames: start_interval_frames,
+      next_row_frame: start_interval_frames,
+    }
+  }
+
+  // Hole columns of the rows that rise on this frame
+  pub fn on_frame(&mut self, frame: u64, width: usize, rng: &mut impl Rng) -> Vec<usize> {
+    let mut holes = Vec::new();
+
+    while frame >= self.next_row_frame {
+      holes.push(self.holes.next_hole(rng, width));
+
+      self.interval_frames =
+        ((self.interval_frames as f32 * self.speedup) as u64).max(self.min_interval_frames);
+      self.next_row_frame += self.interval_frames;
+    }
+
+    holes
+  }
+
+  pub fn frames_until_next_row(&self, frame: u64) -> u64 {
+    self.next_row_frame.saturating_sub(frame)
+  }
+
+  // How close the next row is, from 0.0 just after a row rose to 1.0 when the next one rises
+  pub fn next_row_progress(&self, frame: u64) -> f32 {
+    1.0 - self.frames_until_next_row(frame) as f32 / self.interval_frames as f32
+  }
+
+  pub fn is_warning(&self, frame: u64) -> bool {
+    self.frames_until_next_row(frame) <= WARNING_FRAMES
+  }
+}
+
+impl Default for Survival {
+  fn default() -> Self {
+    Survival::new(Survival::DEFAULT_START_INTERVAL_SECONDS, 0.3)
+  }
+}
@@ -0,0 +1,37 @@
+use crate::game_state::{GameState, FRAMES_PER_SECOND};
+
+// Seconds before the end at which the countdown turns into a warning
+const WARNING_SECONDS: u64 = 10;
+
+#[derive(Clone)]
+pub struct Ultra {
+  pub time_limit_frames: u64,
+}
+
+impl Ultra {
+  pub const DEFAULT_TIME_LIMIT_SECONDS: u32 = 120;
+
+  pub fn new(time_limit_seconds: u32) -> Self {
+    Ultra {
+      time_limit_frames: time_limit_seconds.max(1) as u64 * FRAMES_PER_SECOND as u64,
+    }
+  }
+
+  pub fn frames_left(&self, game_state: &GameState) -> u64 {
+    self.time_limit_frames.saturating_sub(game_state.frame)
+  }
+
+  pub fn is_warning(&self, game_state: &GameState) -> bool {
+    self.frames_left(game_state) <= WARNING_SECONDS * FRAMES_PER_SECOND as u64
+  }
+
+  pub fn is_complete(&self, game_state: &GameState) -> bool {
+    game_state.frame >= self.time_limit_frames
+  }
+}
+
+impl Default for Ultra {
+  fn default() -> Self {
+    Ultra::new(Ultra::DEFAULT_TIME_LIMIT_SECONDS)
+  }
+}
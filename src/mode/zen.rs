@@ -0,0 +1,23 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZenRecovery {
+  ClearTopRows, // Empty rows from the top down until the next piece fits
+  ClearBoard,   // Empty the whole board
+}
+
+#[derive(Clone)]
+pub struct Zen {
+  pub gravity: bool,
+  pub recovery: ZenRecovery,
+}
+
+impl Zen {
+  pub fn new(gravity: bool, recovery: ZenRecovery) -> Self {
+    Zen { gravity, recovery }
+  }
+}
+
+impl Default for Zen {
+  fn default() -> Self {
+    Zen::new(false, ZenRecovery::ClearTopRows)
+  }
+}
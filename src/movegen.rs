@@ -0,0 +1,428 @@
+use crate::board::{Board, Row, MAX_WIDTH};
+use crate::game_event::SpinType;
+use crate::input::Input;
+use crate::piece::Direction::{Down, Left, Right};
+use crate::piece::Piece;
+use std::ops::Range;
+
+// The moves a piece can make before it locks
+pub const MOVES: [Input; 4] = [
+  Input::MoveLeft,
+  Input::MoveRight,
+  Input::SoftDrop,
+  Input::Rotate,
+];
+
+// How a placement can be reached, from easiest to hardest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reach {
+  Drop, // Rotating and shifting, then dropping straight down
+  Tuck, // Needs a shift after soft dropping
+  Spin, // Needs a rotation after soft dropping
+}
+
+// Somewhere a piece can come to rest and lock
+#[derive(Clone)]
+pub struct LockedPlacement {
+  pub piece: Piece,
+  pub rotation: u8, // Clockwise quarter turns from the piece the search started with
+  pub reach: Reach,
+  pub spin: SpinType, // How a lock here scores when the last move was a rotation into it
+}
+
+// Every placement the piece can lock in from where it is now, found by searching all moves and
+// rotations under the same rules as the game. Placements that cover the same cells, like the
+// four rotations of the O piece, are listed once with the easiest way to reach them.
+pub fn locked_placements(piece: &Piece, board: &Board) -> Vec<LockedPlacement> {
+  if !piece.can_stay(board) {
+    return Vec::new();
+  }
+
+  let piece_in = |rotation: u8, x: u8, y: u8| Piece {
+    rotation: (piece.rotation + rotation) % 4,
+    x,
+    y,
+    ..piece.clone()
+  };
+  let layers: [Layer; 4] =
+    [0, 1, 2, 3].map(|rotation| Layer::new(&piece_in(rotation, 0, 0), board));
+
+  // Flood every rotation's positions row by row until nothing new is reachable
+  let mut reachable: [Vec<Row>; 4] = Default::default();
+  for (rotation, layer) in layers.iter().enumerate() {
+    reachable[rotation] = vec![0; layer.fits.len()];
+  }
+  reachable[0][piece.y as usize] = 1 << piece.x;
+
+  let mut changed = true;
+  while changed {
+    changed = false;
+    for rotation in 0..4 {
+      let next_rotation = (rotation + 1) % 4;
+      for y in board.iter_height() {
+        if reachable[rotation][y] == 0 {
+          continue;
+        }
+
+        let row = layers[rotation].shifts(reachable[rotation][y], y);
+        reachable[rotation][y] = row;
+
+        let mut grow = |rotation: usize, y: usize, positions: Row| {
+          if positions & !reachable[rotation][y] != 0 {
+            reachable[rotation][y] |= positions;
+            changed = true;
+          }
+        };
+        grow(next_rotation, y, layers[next_rotation].rotated_into(row, y));
+        if y + 1 < board.height as usize {
+          grow(rotation, y + 1, row & layers[rotation].fits[y + 1]);
+        }
+      }
+    }
+  }
+
+  let mut placements: Vec<LockedPlacement> = Vec::new();
+  let mut layer_placements: [Range<usize>; 4] = Default::default();
+
+  for (rotation, layer) in layers.iter().enumerate() {
+    // Only a rotation with the same cells as an earlier one can repeat its placements
+    let same_cells = (0..rotation)
+      .find(|&earlier| layers[earlier].cells == layer.cells)
+      .map_or(0..0, |earlier| layer_placements[earlier].clone());
+    let first_placement = placements.len();
+    let previous = &reachable[(rotation + 3) % 4];
+    let mut falling = drop_start(piece, &layers, rotation);
+
+    for y in board.iter_height() {
+      let row = reachable[rotation][y];
+      let below = layer.fits.get(y + 1).copied().unwrap_or(0);
+      let rotated_into = layer.rotated_into(previous[y], y);
+      let moved_into = (row << 1 | row >> 1) & layer.fits[y]
+        | if y > 0 {
+          reachable[rotation][y - 1] & layer.fits[y]
+        } else {
+          0
+        };
+
+      let dropped = if y >= piece.y as usize {
+        let dropped = falling & !below;
+        falling &= below;
+        dropped
+      } else {
+        0
+      };
+
+      let mut resting = row & !below;
+      while resting != 0 {
+        let x = resting.trailing_zeros() as u8;
+        let bit = 1 << x;
+        resting &= resting - 1;
+
+        let placed = piece_in(rotation as u8, x, y as u8);
+        let spin = if rotated_into & bit != 0 {
+          placed.t_spin(board)
+        } else {
+          SpinType::None
+        };
+        let reach = if spin != SpinType::None {
+          Reach::Spin
+        } else if dropped & bit != 0 {
+          Reach::Drop
+        } else if moved_into & bit != 0 {
+          Reach::Tuck
+        } else {
+          Reach::Spin
+        };
+
+        let placement = LockedPlacement {
+          piece: placed,
+          rotation: rotation as u8,
+          reach,
+          spin,
+        };
+
+        match placements[same_cells.clone()]
+          .iter_mut()
+          .find(|other| covers_same_cells(&other.piece, &placement.piece))
+        {
+          Some(other) if placement.reach < other.reach => *other = placement,
+          Some(_) => {}
+          None => placements.push(placement),
+        }
+      }
+    }
+
+    layer_placements[rotation] = first_placement..placements.len();
+  }
+
+  placements
+}
+
+// Where one rotation of a piece fits on the board, a row of x positions for every y
+pub(crate) struct Layer {
+  pub cells: [[u8; 4]; 4],
+  pub fits: Vec<Row>,
+  pub max_x: u8,
+}
+
+impl Layer {
+  pub fn new(piece: &Piece, board: &Board) -> Self {
+    let shape = piece.shape();
+    let rows = board.rows();
+    let max_x = board.width - shape.width;
+    let in_bounds = Row::MAX >> (MAX_WIDTH - max_x - 1);
+
+    let fits = board
+      .iter_height()
+      .map(|y| {
+        if y + shape.height as usize > board.height as usize {
+          return 0;
+        }
+
+        shape.iter_height().fold(in_bounds, |fits, dy| {
+          let mut cells = shape.row_mask(dy);
+          let mut blocked = 0;
+          while cells != 0 {
+            blocked |= rows[y + dy] >> cells.trailing_zeros();
+            cells &= cells - 1;
+          }
+          fits & !blocked
+        })
+      })
+      .collect();
+
+    Layer {
+      cells: shape.cells,
+      fits,
+      max_x,
+    }
+  }
+
+  // Everything the positions can shift to along row y
+  fn shifts(&self, mut positions: Row, y: usize) -> Row {
+    loop {
+      let shifted = (positions | positions << 1 | positions >> 1) & self.fits[y];
+      if shifted == positions {
+        return positions;
+      }
+      positions = shifted;
+    }
+  }
+
+  // Where positions of the rotation before this one end up after rotating into this one, pushed
+  // back onto the board the way rotate_piece does
+  fn rotated_into(&self, positions: Row, y: usize) -> Row {
+    let in_bounds = Row::MAX >> (MAX_WIDTH - self.max_x - 1);
+    let pushed_back = if positions & !in_bounds != 0 {
+      1 << self.max_x
+    } else {
+      0
+    };
+    (positions & in_bounds | pushed_back) & self.fits[y]
+  }
+}
+
+// Positions on the spawn row a rotation can shift to after rotating there first, where dropping
+// straight down starts from
+fn drop_start(piece: &Piece, layers: &[Layer; 4], rotation: usize) -> Row {
+  let y = piece.y as usize;
+  let mut positions: Row = 1 << piece.x;
+  for layer in layers.iter().take(rotation + 1).skip(1) {
+    positions = layer.rotated_into(positions, y);
+  }
+  layers[rotation].shifts(positions, y)
+}
+
+// Apply one of MOVES the way the game does, returns whether it succeeded
+pub fn apply_move(piece: &mut Piece, input: Input, board: &Board) -> bool {
+  match input {
+    Input::MoveLeft => piece.move_piece(Left, board),
+    Input::MoveRight => piece.move_piece(Right, board),
+    Input::SoftDrop => piece.move_piece(Down, board),
+    Input::Rotate => piece.rotate_piece(board),
+    Input::HardDrop | Input::Hold => false,
+  }
+}
+
+pub fn covers_same_cells(a: &Piece, b: &Piece) -> bool {
+  a.x == b.x && a.y == b.y && a.shape().cells == b.shape().cells
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::spawned;
+  use crate::shape::{Color, PieceKind};
+  use rand::rngs::StdRng;
+  use rand::{Rng, SeedableRng};
+  use std::collections::{HashSet, VecDeque};
+
+  // What a placement comes down to, without the rotation it was reached with
+  type Found = (u8, u8, [[u8; 4]; 4], Reach, SpinType);
+
+  fn found(placements: &[LockedPlacement]) -> Vec<Found> {
+    let mut found: Vec<Found> = placements
+      .iter()
+      .map(|placement| {
+        let piece = &placement.piece;
+        let cells = piece.shape().cells;
+        (piece.x, piece.y, cells, placement.reach, placement.spin)
+      })
+      .collect();
+    found.sort_by_key(|&(x, y, cells, ..)| (y, x, cells));
+    found
+  }
+
+  // The same search one move at a time, breadth first over every rotation, x and y
+  fn search_moves(piece: &Piece, board: &Board) -> Vec<Found> {
+    let mut visited = HashSet::from([(piece.rotation, piece.x, piece.y)]);
+    let mut queue = VecDeque::from([piece.clone()]);
+    let mut rotated_into = HashSet::new();
+    let mut moved_into = HashSet::new();
+    let mut resting = Vec::new();
+
+    while let Some(current) = queue.pop_front() {
+      if !current.can_move(Down, board) {
+        resting.push(current.clone());
+      }
+      for input in MOVES {
+        let mut next = current.clone();
+        if !apply_move(&mut next, input, board) {
+          continue;
+        }
+        let state = (next.rotation, next.x, next.y);
+        if input == Input::Rotate {
+          rotated_into.insert(state);
+        } else {
+          moved_into.insert(state);
+        }
+        if visited.insert(state) {
+          queue.push_back(next);
+        }
+      }
+    }
+
+    let dropped = dropped_to(piece, board);
+    let mut placements: Vec<LockedPlacement> = Vec::new();
+    for placed in resting {
+      let state = (placed.rotation, placed.x, placed.y);
+      let spin = if rotated_into.contains(&state) {
+        placed.t_spin(board)
+      } else {
+        SpinType::None
+      };
+      let reach = if spin != SpinType::None {
+        Reach::Spin
+      } else if dropped.contains(&state) {
+        Reach::Drop
+      } else if moved_into.contains(&state) {
+        Reach::Tuck
+      } else {
+        Reach::Spin
+      };
+
+      let placement = LockedPlacement {
+        rotation: 0,
+        piece: placed,
+        reach,
+        spin,
+      };
+      match placements
+        .iter_mut()
+        .find(|other| covers_same_cells(&other.piece, &placement.piece))
+      {
+        Some(other) if placement.reach < other.reach => *other = placement,
+        Some(_) => {}
+        None => placements.push(placement),
+      }
+    }
+
+    found(&placements)
+  }
+
+  // Where rotating, then shifting, then dropping straight down can take the piece
+  fn dropped_to(piece: &Piece, board: &Board) -> HashSet<(u8, u8, u8)> {
+    let mut dropped = HashSet::new();
+    for rotations in 0..4 {
+      for x in board.iter_width() {
+        let mut placed = piece.clone();
+        let reached = (0..rotations).all(|_| placed.rotate_piece(board))
+          && (0..board.width as usize).all(|_| {
+            placed.x as usize == x
+              || placed.move_piece(if (placed.x as usize) < x { Right } else { Left }, board)
+          })
+          && placed.x as usize == x;
+        if reached {
+          while placed.move_piece(Down, board) {}
+          dropped.insert((placed.rotation, placed.x, placed.y));
+        }
+      }
+    }
+    dropped
+  }
+
+  fn spawn(kind: PieceKind, board: &Board) -> Option<Piece> {
+    let piece = Piece {
+      kind,
+      rotation: 0,
+      x: 0,
+      y: 0,
+    };
+    spawned(&piece, board)
+  }
+
+  #[test]
+  fn empty_board() {
+    let board = Board::new(10, 20);
+    let count = |kind| locked_placements(&spawn(kind, &board).unwrap(), &board).len();
+
+    assert_eq!(count(PieceKind::O), 9);
+    assert_eq!(count(PieceKind::I), 17);
+    assert_eq!(count(PieceKind::T), 34);
+    assert_eq!(count(PieceKind::S), 17);
+  }
+
+  #[test]
+  fn tuck_under_ledge() {
+    let mut board = Board::new(5, 6);
+    board.set_cell(0, 2, Color::Garbage);
+    board.set_cell(1, 2, Color::Garbage);
+    let placements = locked_placements(&spawn(PieceKind::O, &board).unwrap(), &board);
+
+    let reach_at = |x, y| {
+      placements
+        .iter()
+        .find(|placement| placement.piece.x == x && placement.piece.y == y)
+        .map(|placement| placement.reach)
+    };
+    assert_eq!(reach_at(0, 0), Some(Reach::Drop));
+    assert_eq!(reach_at(0, 4), Some(Reach::Tuck));
+    assert_eq!(reach_at(3, 4), Some(Reach::Drop));
+    assert_eq!(reach_at(1, 4), Some(Reach::Tuck));
+    assert_eq!(placements.len(), 6);
+  }
+
+  #[test]
+  fn matches_searching_moves() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..300 {
+      let mut board = Board::new(rng.gen_range(4..=10), 8);
+      for y in 3..board.height as usize {
+        for x in board.iter_width() {
+          if rng.gen_bool(0.35) {
+            board.set_cell(x, y, Color::Garbage);
+          }
+        }
+      }
+
+      for kind in PieceKind::ALL {
+        let Some(piece) = spawn(kind, &board) else {
+          continue;
+        };
+        let expected = search_moves(&piece, &board);
+        assert_eq!(found(&locked_placements(&piece, &board)), expected);
+      }
+    }
+  }
+}
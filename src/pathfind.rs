@@ -0,0 +1,302 @@
+use crate::board::{Board, Row};
+use crate::game_event::SpinType;
+use crate::input::Input;
+use crate::movegen::{Layer, LockedPlacement};
+use crate::piece::Piece;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// One key press. Holding a key slides the piece as far as it goes, which finesse counts as a
+// single press just like a tap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyPress {
+  Tap(Input),
+  Hold(Input), // MoveLeft, MoveRight or SoftDrop
+}
+
+const KEY_PRESSES: [KeyPress; 7] = [
+  KeyPress::Tap(Input::Rotate),
+  KeyPress::Hold(Input::MoveLeft),
+  KeyPress::Hold(Input::MoveRight),
+  KeyPress::Tap(Input::MoveLeft),
+  KeyPress::Tap(Input::MoveRight),
+  KeyPress::Hold(Input::SoftDrop),
+  KeyPress::Tap(Input::SoftDrop),
+];
+
+// Rotation, x, y and whether the last move was a rotation
+type State = (u8, u8, u8, bool);
+
+// The fewest key presses that take the piece to the target and lock it there, ending with a hard
+// drop. Between paths with as many presses the one with fewer engine inputs wins. Spin targets
+// are reached with a rotation as the last move so the lock scores as a spin.
+pub fn find_path(piece: &Piece, board: &Board, target: &LockedPlacement) -> Option<Vec<KeyPress>> {
+  let layers = layers(piece, board);
+  let start = (0, piece.x, piece.y, false);
+  if !fits(&layers, start) {
+    return None;
+  }
+
+  // At least the presses still needed from a state: turns into a rotation with the target's
+  // cells, since rotating only goes clockwise, or one press when the column is off. None below
+  // the target, as nothing moves a piece up.
+  let target_cells = target.piece.shape().cells;
+  let target_rotations: Vec<u8> = (0..4)
+    .filter(|&rotation| layers[rotation as usize].cells == target_cells)
+    .collect();
+  let remaining = |(rotation, x, y, _): State| {
+    if y > target.piece.y {
+      return None;
+    }
+    let turns = target_rotations
+      .iter()
+      .map(|&target_rotation| (target_rotation + 4 - rotation) % 4)
+      .min()
+      .unwrap_or(0);
+    Some(turns.max(u8::from(x != target.piece.x)) as u32)
+  };
+  let start_remaining = remaining(start)?;
+
+  // Cheapest first by key presses with the ones still needed added, then by engine inputs.
+  // States are kept in flat tables indexed by rotation, y, x and the rotated flag, only for the
+  // rows between the piece and the target and with the flag only when looking for a spin.
+  let spin = target.spin != SpinType::None;
+  let rows = (target.piece.y + 1).saturating_sub(piece.y) as usize;
+  let index = |(rotation, x, y, rotated): State| {
+    let cell = (rotation as usize * rows + (y - piece.y) as usize) * board.width as usize;
+    (cell + x as usize) * (1 + spin as usize) + rotated as usize
+  };
+  let states = 4 * rows * board.width as usize * (1 + spin as usize);
+  let mut costs: Vec<Option<(u32, u32)>> = vec![None; states];
+  let mut came_from: Vec<Option<(State, KeyPress)>> = vec![None; states];
+
+  costs[index(start)] = Some((0, 0));
+  let mut queue = BinaryHeap::from([Reverse(((start_remaining, 0), (0, 0), start))]);
+
+  while let Some(Reverse((_, cost, state))) = queue.pop() {
+    if costs[index(state)].is_some_and(|best| best < cost) {
+      continue;
+    }
+
+    if reaches_target(&layers, state, target) {
+      let mut path = vec![KeyPress::Tap(Input::HardDrop)];
+      let mut step = state;
+      while let Some((previous, key_press)) = came_from[index(step)] {
+        path.push(key_press);
+        step = previous;
+      }
+
+      path.reverse();
+      return Some(path);
+    }
+
+    for key_press in KEY_PRESSES {
+      let mut inputs = 0;
+      let mut next_state = press(&layers, state, key_press, |_| inputs += 1);
+      if inputs == 0 {
+        continue;
+      }
+      // Only spins care how the piece got somewhere
+      next_state.3 &= spin;
+      let Some(next_remaining) = remaining(next_state) else {
+        continue;
+      };
+
+      let next_cost = (cost.0 + 1, cost.1 + inputs);
+      if costs[index(next_state)].is_none_or(|best| next_cost < best) {
+        costs[index(next_state)] = Some(next_cost);
+        came_from[index(next_state)] = Some((state, key_press));
+        let estimate = (next_cost.0 + next_remaining, next_cost.1);
+        queue.push(Reverse((estimate, next_cost, next_state)));
+      }
+    }
+  }
+
+  None
+}
+
+// The engine inputs for the key presses, with every cell of a held key as its own input
+pub fn path_inputs(piece: &Piece, board: &Board, path: &[KeyPress]) -> Vec<Input> {
+  let layers = layers(piece, board);
+  let mut state = (0, piece.x, piece.y, false);
+  let mut inputs = Vec::new();
+  for &key_press in path {
+    state = press(&layers, state, key_press, |input| inputs.push(input));
+  }
+  inputs
+}
+
+// Where every rotation of the piece fits, by turns from the piece's own rotation
+fn layers(piece: &Piece, board: &Board) -> [Layer; 4] {
+  [0, 1, 2, 3].map(|rotation| {
+    let rotated = Piece {
+      rotation: (piece.rotation + rotation) % 4,
+      ..piece.clone()
+    };
+    Layer::new(&rotated, board)
+  })
+}
+
+fn fits(layers: &[Layer; 4], (rotation, x, y, _): State) -> bool {
+  let fits = &layers[rotation as usize].fits;
+  (x as u32) < Row::BITS && fits.get(y as usize).is_some_and(|&row| row >> x & 1 == 1)
+}
+
+// Apply a key press the way the game moves pieces, passing on every engine input it took. A hard
+// drop always counts.
+fn press(
+  layers: &[Layer; 4],
+  mut state: State,
+  key_press: KeyPress,
+  mut on_input: impl FnMut(Input),
+) -> State {
+  let moved = |(rotation, x, y, _): State, input: Input| {
+    let next = match input {
+      Input::MoveLeft => (rotation, x.checked_sub(1)?, y, false),
+      Input::MoveRight => (rotation, x + 1, y, false),
+      Input::SoftDrop => (rotation, x, y + 1, false),
+      Input::Rotate => {
+        // Pushed back onto the board when rotating past a wall
+        let rotation = (rotation + 1) % 4;
+        (rotation, x.min(layers[rotation as usize].max_x), y, true)
+      }
+      Input::HardDrop | Input::Hold => return None,
+    };
+    fits(layers, next).then_some(next)
+  };
+
+  match key_press {
+    KeyPress::Tap(Input::HardDrop) => {
+      while let Some(next) = moved(state, Input::SoftDrop) {
+        state = next;
+      }
+      on_input(Input::HardDrop);
+    }
+    KeyPress::Tap(input) => {
+      if let Some(next) = moved(state, input) {
+        state = next;
+        on_input(input);
+      }
+    }
+    KeyPress::Hold(input) => {
+      while let Some(next) = moved(state, input) {
+        state = next;
+        on_input(input);
+      }
+    }
+  }
+  state
+}
+
+fn reaches_target(layers: &[Layer; 4], state: State, target: &LockedPlacement) -> bool {
+  let (rotation, x, y, rotated_last) = state;
+  let same_cells = layers[rotation as usize].cells == target.piece.shape().cells;
+
+  if target.spin != SpinType::None {
+    return rotated_last && same_cells && x == target.piece.x && y == target.piece.y;
+  }
+
+  // Dropping only changes y
+  if !same_cells || x != target.piece.x || y > target.piece.y {
+    return false;
+  }
+
+  let (_, _, dropped_y, _) = press(layers, state, KeyPress::Tap(Input::HardDrop), |_| {});
+  dropped_y == target.piece.y
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bot::spawned;
+  use crate::movegen::{apply_move, covers_same_cells, locked_placements};
+  use crate::shape::{Color, PieceKind};
+  use rand::rngs::StdRng;
+  use rand::{Rng, SeedableRng};
+
+  fn spawn(kind: PieceKind, board: &Board) -> Option<Piece> {
+    let piece = Piece {
+      kind,
+      rotation: 0,
+      x: 0,
+      y: 0,
+    };
+    spawned(&piece, board)
+  }
+
+  // Where the inputs leave the piece when the game plays them one at a time
+  fn play(piece: &Piece, board: &Board, inputs: &[Input]) -> (Piece, bool) {
+    let mut piece = piece.clone();
+    let mut rotated_last = false;
+    for &input in inputs {
+      if input == Input::HardDrop {
+        while apply_move(&mut piece, Input::SoftDrop, board) {
+          rotated_last = false;
+        }
+      } else {
+        assert!(
+          apply_move(&mut piece, input, board),
+          "{input:?} did not move the piece"
+        );
+        rotated_last = input == Input::Rotate;
+      }
+    }
+    (piece, rotated_last)
+  }
+
+  #[test]
+  fn fewest_presses_on_an_empty_board() {
+    let board = Board::new(10, 20);
+    let piece = spawn(PieceKind::O, &board).unwrap();
+    let path_to = |x| {
+      let placement = locked_placements(&piece, &board)
+        .into_iter()
+        .find(|placement| placement.piece.x == x)
+        .unwrap();
+      find_path(&piece, &board, &placement).unwrap()
+    };
+
+    let hard_drop = KeyPress::Tap(Input::HardDrop);
+    assert_eq!(path_to(4), [hard_drop]);
+    assert_eq!(path_to(3), [KeyPress::Tap(Input::MoveLeft), hard_drop]);
+    assert_eq!(path_to(0), [KeyPress::Hold(Input::MoveLeft), hard_drop]);
+    let (tap_right, hold_right) = (
+      KeyPress::Tap(Input::MoveRight),
+      KeyPress::Hold(Input::MoveRight),
+    );
+    assert_eq!(path_to(6), [tap_right, tap_right, hard_drop]);
+    assert_eq!(
+      path_to(7),
+      [hold_right, KeyPress::Tap(Input::MoveLeft), hard_drop]
+    );
+    assert_eq!(path_to(8), [hold_right, hard_drop]);
+  }
+
+  #[test]
+  fn paths_lock_on_every_placement() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for _ in 0..100 {
+      let mut board = Board::new(rng.gen_range(4..=10), 8);
+      for y in 3..board.height as usize {
+        for x in board.iter_width() {
+          if rng.gen_bool(0.35) {
+            board.set_cell(x, y, Color::Garbage);
+          }
+        }
+      }
+
+      for kind in PieceKind::ALL {
+        let Some(piece) = spawn(kind, &board) else {
+          continue;
+        };
+        for placement in locked_placements(&piece, &board) {
+          let path = find_path(&piece, &board, &placement).unwrap();
+          let (locked, rotated_last) = play(&piece, &board, &path_inputs(&piece, &board, &path));
+          assert!(covers_same_cells(&locked, &placement.piece));
+          assert!(placement.spin == SpinType::None || rotated_last);
+        }
+      }
+    }
+  }
+}
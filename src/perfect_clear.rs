@@ -0,0 +1,532 @@
+use crate::board::{Board, Row};
+use crate::bot::spawned;
+use crate::movegen::locked_placements;
+use crate::piece::Piece;
+use crate::randomizer::Randomizer;
+use crate::shape::{Color, PieceKind};
+use std::collections::{HashMap, HashSet};
+
+// Perfect clears are looked for at most this many rows up
+pub const MAX_PC_HEIGHT: u8 = 6;
+
+// Unknown pieces pc_chance looks ahead. Every one multiplies the orders to search by up to 7, and
+// two already take seconds from an empty board.
+pub const MAX_UNKNOWN_PIECES: usize = 2;
+
+// Rows kept above the cleared area for pieces to spawn and turn in
+const SPAWN_ROWS: u8 = 4;
+
+// One placement of a perfect clear, where the piece locks on the board as it is at that point
+#[derive(Clone)]
+pub struct PcStep {
+  pub hold: bool, // Hold is pressed before the piece is played
+  pub piece: Piece,
+}
+
+// Of all the orders the unknown pieces can still come in, how many allow a perfect clear
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PcChance {
+  pub solvable: u64,
+  pub sequences: u64,
+}
+
+// Why pc_chance has no chance to give
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcChanceError {
+  NoClear,               // The board or stack does not fit the height, see pieces_needed
+  TooManyUnknown(usize), // The clear needs this many pieces past the queue, over MAX_UNKNOWN_PIECES
+}
+
+impl PcChance {
+  pub fn probability(&self) -> f64 {
+    if self.sequences == 0 {
+      return 0.0;
+    }
+    self.solvable as f64 / self.sequences as f64
+  }
+}
+
+// Pieces needed to fill the bottom height rows, None when the board or the stack does not fit
+// that height or the empty cells cannot be split into pieces
+pub fn pieces_needed(board: &Board, height: u8) -> Option<usize> {
+  if height == 0
+    || height > MAX_PC_HEIGHT
+    || height > board.height
+    || board.max_height() > height as u32
+  {
+    return None;
+  }
+
+  let filled: u32 = board.rows().iter().map(|row| row.count_ones()).sum();
+  let empty = board.width as u32 * height as u32 - filled;
+  empty.is_multiple_of(4).then_some(empty as usize / 4)
+}
+
+// Perfect clears of the bottom height rows that play pieces from the queue, using hold where it
+// helps. Returns up to max_solutions placement sequences, with positions on the given board.
+pub fn solve(
+  board: &Board,
+  queue: &[PieceKind],
+  hold: Option<PieceKind>,
+  height: u8,
+  max_solutions: usize,
+) -> Vec<Vec<PcStep>> {
+  if pieces_needed(board, height).is_none() {
+    return Vec::new();
+  }
+
+  let search_board = search_board(board, height);
+  let mut search = Search {
+    max_solutions,
+    solutions: Vec::new(),
+    steps: Vec::new(),
+    failed: HashSet::new(),
+  };
+  search.search(&search_board, height, hold, queue);
+
+  // Back to the rows of the board we were given. Pieces only lock in the rows being cleared,
+  // below the spawn rows.
+  for solution in search.solutions.iter_mut() {
+    for step in solution.iter_mut() {
+      step.piece.y = board.height - height + (step.piece.y - SPAWN_ROWS);
+    }
+  }
+  search.solutions
+}
+
+// The chance of a perfect clear of the bottom height rows when the pieces after the queue come
+// from the randomizer, left as it was after drawing the last queued piece, and every order it
+// can draw is equally likely.
+pub fn pc_chance(
+  board: &Board,
+  queue: &[PieceKind],
+  hold: Option<PieceKind>,
+  randomizer: &Randomizer,
+  height: u8,
+) -> Result<PcChance, PcChanceError> {
+  let needed = pieces_needed(board, height).ok_or(PcChanceError::NoClear)?;
+
+  // Every placement draws a piece, and a first hold into an empty slot draws one more
+  let draws = needed + hold.is_none() as usize;
+  let queue = &queue[..queue.len().min(draws)];
+  let unknown = draws - queue.len();
+  if unknown > MAX_UNKNOWN_PIECES {
+    return Err(PcChanceError::TooManyUnknown(unknown));
+  }
+
+  let continuations = match randomizer {
+    Randomizer::Uniform => uniform_sequences(unknown),
+    Randomizer::Bag(bag_remaining) => bag_sequences(bag_remaining, unknown),
+  };
+  let sequences: Vec<Vec<PieceKind>> = continuations
+    .into_iter()
+    .map(|continuation| queue.iter().copied().chain(continuation).collect())
+    .collect();
+
+  let mut search = ChanceSearch::new(&sequences);
+  let solvable = search.search(&search_board(board, height), height, hold, 0);
+
+  Ok(PcChance {
+    solvable: solvable.len() as u64,
+    sequences: sequences.len() as u64,
+  })
+}
+
+// Every sequence of length pieces, each any kind
+fn uniform_sequences(length: usize) -> Vec<Vec<PieceKind>> {
+  (0..length).fold(vec![Vec::new()], |sequences, _| {
+    sequences
+      .iter()
+      .flat_map(|sequence| {
+        PieceKind::ALL.map(|kind| {
+          let mut sequence = sequence.clone();
+          sequence.push(kind);
+          sequence
+        })
+      })
+      .collect()
+  })
+}
+
+// Every order the next length pieces can come out of a 7-bag in, all equally likely.
+// bag_remaining is what is left of the current bag, empty when a new one starts.
+fn bag_sequences(bag_remaining: &[PieceKind], length: usize) -> Vec<Vec<PieceKind>> {
+  if length == 0 {
+    return vec![Vec::new()];
+  }
+
+  let bag = if bag_remaining.is_empty() {
+    &PieceKind::ALL[..]
+  } else {
+    bag_remaining
+  };
+
+  let mut sequences = Vec::new();
+  for (index, &kind) in bag.iter().enumerate() {
+    let rest: Vec<PieceKind> = bag
+      .iter()
+      .enumerate()
+      .filter(|&(other, _)| other != index)
+      .map(|(_, &kind)| kind)
+      .collect();
+
+    for mut sequence in bag_sequences(&rest, length - 1) {
+      sequence.insert(0, kind);
+      sequences.push(sequence);
+    }
+  }
+  sequences
+}
+
+// The bottom height rows of the board with SPAWN_ROWS empty rows above them, without colours.
+// The board needs to be at least height rows high.
+fn search_board(board: &Board, height: u8) -> Board {
+  let mut search_board = Board::new(board.width, height + SPAWN_ROWS).without_colors();
+  let first_row = (board.height - height) as usize;
+
+  for y in 0..height as usize {
+    for x in board.iter_width() {
+      if board.is_filled(x, first_row + y) {
+        search_board.set_cell(x, SPAWN_ROWS as usize + y, Color::Garbage);
+      }
+    }
+  }
+  search_board
+}
+
+// Depth first search through one known queue, collecting the placements of every clear found
+struct Search {
+  max_solutions: usize,
+  solutions: Vec<Vec<PcStep>>,
+  steps: Vec<PcStep>,
+  // Positions known to have no perfect clear: rows, hold and pieces left in the queue
+  failed: HashSet<(Vec<Row>, Option<PieceKind>, usize)>,
+}
+
+impl Search {
+  // Whether a perfect clear was found from here
+  fn search(
+    &mut self,
+    board: &Board,
+    lines: u8,
+    hold: Option<PieceKind>,
+    queue: &[PieceKind],
+  ) -> bool {
+    if lines == 0 {
+      self.solutions.push(self.steps.clone());
+      return true;
+    }
+
+    let key = (board.rows().to_vec(), hold, queue.len());
+    if self.failed.contains(&key) || !can_still_clear(board, lines, hold, queue.len()) {
+      return false;
+    }
+
+    // The piece to play, the hold after it, the queue after it and whether hold was pressed
+    let mut choices = Vec::new();
+    if let Some((&current, rest)) = queue.split_first() {
+      choices.push((current, hold, rest, false));
+      match hold {
+        Some(held) if held != current => choices.push((held, Some(current), rest, true)),
+        None => {
+          if let Some((&next, rest)) = rest.split_first() {
+            if next != current {
+              choices.push((next, Some(current), rest, true));
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+
+    let mut found = false;
+    for (kind, hold, rest, pressed_hold) in choices {
+      for piece in placements(kind, board, lines) {
+        let (next_board, cleared) = lock(board, &piece);
+
+        self.steps.push(PcStep {
+          hold: pressed_hold,
+          piece,
+        });
+        found |= self.search(&next_board, lines - cleared, hold, rest);
+        self.steps.pop();
+
+        if self.solutions.len() >= self.max_solutions {
+          return found;
+        }
+      }
+    }
+
+    if !found {
+      self.failed.insert(key);
+    }
+    found
+  }
+}
+
+// Searches every possible queue at once. A position's result is the set of queues that can still
+// finish a perfect clear from it, so positions shared between queues are only searched once.
+struct ChanceSearch {
+  length: usize,
+  // For every queue position and piece kind, the queues with that kind there
+  kind_at: Vec<[SequenceSet; 7]>,
+  everything: SequenceSet,
+  results: HashMap<(Vec<Row>, Option<PieceKind>, usize), SequenceSet>,
+}
+
+impl ChanceSearch {
+  fn new(sequences: &[Vec<PieceKind>]) -> Self {
+    let length = sequences.first().map_or(0, Vec::len);
+    let kind_at = (0..length)
+      .map(|position| {
+        PieceKind::ALL.map(|kind| {
+          let mut set = SequenceSet::new(sequences.len());
+          for (index, sequence) in sequences.iter().enumerate() {
+            if sequence[position] == kind {
+              set.insert(index);
+            }
+          }
+          set
+        })
+      })
+      .collect();
+
+    let mut everything = SequenceSet::new(sequences.len());
+    for index in 0..sequences.len() {
+      everything.insert(index);
+    }
+
+    ChanceSearch {
+      length,
+      kind_at,
+      everything,
+      results: HashMap::new(),
+    }
+  }
+
+  // The queues that can finish a perfect clear from this position, position being how many
+  // pieces have been drawn
+  fn search(
+    &mut self,
+    board: &Board,
+    lines: u8,
+    hold: Option<PieceKind>,
+    position: usize,
+  ) -> SequenceSet {
+    if lines == 0 {
+      return self.everything.clone();
+    }
+
+    let key = (board.rows().to_vec(), hold, position);
+    if let Some(result) = self.results.get(&key) {
+      return result.clone();
+    }
+
+    let mut result = SequenceSet::new(self.everything.capacity());
+    if can_still_clear(board, lines, hold, self.length - position) {
+      // Several choices can play the same kind
+      let mut kind_placements: [Option<Vec<Piece>>; 7] = Default::default();
+
+      for (kind, next_hold, next_position, queues) in self.choices(hold, position) {
+        let pieces =
+          kind_placements[kind as usize].get_or_insert_with(|| placements(kind, board, lines));
+        for piece in pieces.iter() {
+          if queues.is_subset(&result) {
+            break;
+          }
+
+          let (next_board, cleared) = lock(board, piece);
+          let mut finished = self.search(&next_board, lines - cleared, next_hold, next_position);
+          finished.intersect(&queues);
+          result.union(&finished);
+        }
+      }
+    }
+
+    self.results.insert(key, result.clone());
+    result
+  }
+
+  // The piece to play, the hold after it, how many pieces have been drawn after it and the
+  // queues that allow it
+  fn choices(
+    &self,
+    hold: Option<PieceKind>,
+    position: usize,
+  ) -> Vec<(PieceKind, Option<PieceKind>, usize, SequenceSet)> {
+    let mut choices = Vec::new();
+    if position >= self.length {
+      return choices;
+    }
+
+    for current in PieceKind::ALL {
+      let queues = &self.kind_at[position][current as usize];
+      if queues.is_empty() {
+        continue;
+      }
+
+      choices.push((current, hold, position + 1, queues.clone()));
+      match hold {
+        Some(held) if held != current => {
+          choices.push((held, Some(current), position + 1, queues.clone()))
+        }
+        None if position + 1 < self.length => {
+          for next in PieceKind::ALL.into_iter().filter(|&next| next != current) {
+            let mut both = queues.clone();
+            both.intersect(&self.kind_at[position + 1][next as usize]);
+            if !both.is_empty() {
+              choices.push((next, Some(current), position + 2, both));
+            }
+          }
+        }
+        _ => {}
+      }
+    }
+    choices
+  }
+}
+
+// A set of queues by index, as bits
+#[derive(Clone)]
+struct SequenceSet {
+  bits: Vec<u64>,
+}
+
+impl SequenceSet {
+  fn new(capacity: usize) -> Self {
+    SequenceSet {
+      bits: vec![0; capacity.div_ceil(64)],
+    }
+  }
+
+  fn capacity(&self) -> usize {
+    self.bits.len() * 64
+  }
+
+  fn insert(&mut self, index: usize) {
+    self.bits[index / 64] |= 1 << (index % 64);
+  }
+
+  fn len(&self) -> usize {
+    self
+      .bits
+      .iter()
+      .map(|word| word.count_ones() as usize)
+      .sum()
+  }
+
+  fn is_empty(&self) -> bool {
+    self.bits.iter().all(|&word| word == 0)
+  }
+
+  fn is_subset(&self, other: &SequenceSet) -> bool {
+    self.bits.iter().zip(&other.bits).all(|(a, b)| a & !b == 0)
+  }
+
+  fn intersect(&mut self, other: &SequenceSet) {
+    for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+      *a &= b;
+    }
+  }
+
+  fn union(&mut self, other: &SequenceSet) {
+    for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+      *a |= b;
+    }
+  }
+}
+
+// Cheap checks that rule a position out: enough pieces left, and every stretch of columns between
+// completely filled ones having room for whole pieces
+fn can_still_clear(board: &Board, lines: u8, hold: Option<PieceKind>, queued: usize) -> bool {
+  let area = &board.rows()[(board.height - lines) as usize..];
+  let filled: usize = area.iter().map(|row| row.count_ones() as usize).sum();
+  let empty = board.width as usize * lines as usize - filled;
+  if empty / 4 > queued + hold.is_some() as usize {
+    return false;
+  }
+
+  // Pieces cannot reach across a column that is already filled
+  let mut stretch_empty: usize = 0;
+  for x in board.iter_width() {
+    let column_empty = area.iter().filter(|&&row| row >> x & 1 == 0).count();
+    if column_empty == 0 {
+      if !stretch_empty.is_multiple_of(4) {
+        return false;
+      }
+      stretch_empty = 0;
+    }
+    stretch_empty += column_empty;
+  }
+  stretch_empty.is_multiple_of(4)
+}
+
+// Where a piece of this kind can lock without sticking out above the rows being cleared
+fn placements(kind: PieceKind, board: &Board, lines: u8) -> Vec<Piece> {
+  let Some(piece) = spawned(&Piece::new(kind), board) else {
+    return Vec::new();
+  };
+
+  locked_placements(&piece, board)
+    .into_iter()
+    .map(|placement| placement.piece)
+    .filter(|piece| piece.y >= board.height - lines)
+    .collect()
+}
+
+fn lock(board: &Board, piece: &Piece) -> (Board, u8) {
+  let mut board = board.clone();
+  board.merge_piece(piece);
+  let cleared = board.clear_full_lines().len() as u8;
+  (board, cleared)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use PieceKind::{I, O, T};
+
+  #[test]
+  fn boards_as_short_as_the_clear() {
+    assert!(solve(&Board::new(10, 4), &[I], None, 4, 1).is_empty());
+    assert!(solve(&Board::new(10, 3), &[I], None, 4, 1).is_empty());
+
+    let solution = solve(&Board::new(4, 2), &[I, I], None, 2, 1).pop().unwrap();
+    let rows: Vec<u8> = solution.iter().map(|step| step.piece.y).collect();
+    assert_eq!(rows, vec![1, 1]);
+  }
+
+  #[test]
+  fn solution_rows_on_a_tall_board() {
+    let solution = solve(&Board::new(4, 20), &[O, O, I], None, 3, 1)
+      .pop()
+      .unwrap();
+    let rows: Vec<u8> = solution.iter().map(|step| step.piece.y).collect();
+    // The two O pieces clear both rows, so the I lands on the floor again
+    assert_eq!(rows, vec![18, 18, 19]);
+  }
+
+  #[test]
+  fn chance_on_a_short_board() {
+    let bag = Randomizer::seven_bag();
+    let chance = pc_chance(&Board::new(4, 6), &[I, I, I, I], None, &bag, 4).unwrap();
+    assert_eq!(chance.solvable, 7);
+    assert_eq!(chance.sequences, 7);
+
+    let no_clear = pc_chance(&Board::new(4, 3), &[I, I, I], None, &bag, 4);
+    assert_eq!(no_clear, Err(PcChanceError::NoClear));
+  }
+
+  #[test]
+  fn chance_per_randomizer() {
+    let board = Board::new(4, 20);
+    // Two rows after an O with an I in hold, finished by an O or an I as the unknown piece
+    let bag = pc_chance(&board, &[O], Some(I), &Randomizer::Bag(vec![O, T]), 2).unwrap();
+    assert_eq!((bag.solvable, bag.sequences), (1, 2));
+    let uniform = pc_chance(&board, &[O], Some(I), &Randomizer::Uniform, 2).unwrap();
+    assert_eq!((uniform.solvable, uniform.sequences), (2, 7));
+
+    let unknown = pc_chance(&Board::new(10, 20), &[O], None, &Randomizer::Uniform, 4);
+    assert_eq!(unknown, Err(PcChanceError::TooManyUnknown(10)));
+  }
+}
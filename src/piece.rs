@@ -1,25 +1,37 @@
 use crate::board::Board;
-use crate::shape::{get_random_shape, Color, Shape};
+use crate::game_event::SpinType;
+use crate::shape::{PieceKind, Shape};
+use rand::seq::SliceRandom;
+use rand::Rng;
 
 #[derive(Clone)]
 pub struct Piece {
-  pub shape: Shape,
+  pub kind: PieceKind,
+  pub rotation: u8, // Clockwise quarter turns from the spawn orientation
   pub x: u8,
   pub y: u8,
 }
 
 #[allow(deprecated)]
 impl Piece {
-  pub fn random_piece() -> Piece {
-    let current_shape = get_random_shape();
-
+  // The piece in its spawn orientation at the top of a standard width board
+  pub fn new(kind: PieceKind) -> Piece {
     Piece {
-      x: 5 - current_shape.width / 2,
+      kind,
+      rotation: 0,
+      x: 5 - kind.shape(0).width / 2,
       y: 0,
-      shape: current_shape,
     }
   }
 
+  pub fn random_piece(rng: &mut impl Rng) -> Piece {
+    Piece::new(*PieceKind::ALL.choose(rng).expect("No shapes!"))
+  }
+
+  pub fn shape(&self) -> &'static Shape {
+    self.kind.shape(self.rotation)
+  }
+
   pub fn can_move(&self, direction: Direction, board: &Board) -> bool {
     let (dx, dy) = match direction {
       Direction::Left => (-1, 0),
@@ -27,103 +39,116 @@ impl Piece {
       Direction::Down => (0, 1),
     };
 
-    for y in self.shape.iter_height() {
-      for x in self.shape.iter_width() {
-        if self.shape.cells[y][x] == 1 {
-          // Only check occupied cells in the shape
-          let new_x = self.x as i8 + x as i8 + dx;
-          let new_y = self.y as i8 + y as i8 + dy;
-
-          // Check horizontal boundaries
-          if new_x < 0 || new_x >= board.width as i8 {
-            return false;
-          }
-
-          // Check vertical boundaries
-          if new_y < 0 || new_y >= board.height as i8 {
-            return false;
-          }
-
-          // Check if the space is occupied on the board
-          if board.grid[new_y as usize][new_x as usize] != Color::None {
-            return false;
-          }
-        }
-      }
+    board.fits(self.shape(), self.x as i32 + dx, self.y as i32 + dy)
+  }
+
+  // Move the piece in the given direction, returns whether it moved
+  pub fn move_piece(&mut self, direction: Direction, board: &Board) -> bool {
+    if !self.can_move(direction, board) {
+      return false;
+    }
+
+    match direction {
+      Direction::Left => self.x -= 1,
+      Direction::Right => self.x += 1,
+      Direction::Down => self.y += 1,
     }
     true
   }
 
-  // Move the piece in the given direction
-  pub fn move_piece(&mut self, direction: Direction, board: &Board) {
-    if self.can_move(direction, board) {
-      match direction {
-        Direction::Left => self.x -= 1,
-        Direction::Right => self.x += 1,
-        Direction::Down => self.y += 1,
-      }
+  // Rotate the piece clockwise, returns whether the rotation fit. A piece rotated past a wall
+  // is shifted back onto the board, and left where it was when it does not fit.
+  pub fn rotate_piece(&mut self, board: &Board) -> bool {
+    let rotation = (self.rotation + 1) % 4;
+    let shape = self.kind.shape(rotation);
+    let x = self.x.min(board.width.saturating_sub(shape.width));
+
+    if !board.fits(shape, x as i32, self.y as i32) {
+      return false;
     }
+
+    self.rotation = rotation;
+    self.x = x;
+    true
   }
 
-  pub fn rotate_piece(&mut self, board: &Board) {
-    let original_shape = self.shape.clone();
-    let rotated_shape = self.shape.rotate();
-    self.shape = rotated_shape;
-
-    // If out of bounds, shift the piece back onto the board
-    if !self.is_within_bounds(board) {
-      // Shift to the right if it's out of the left boundary
-      while (self.x as i8) < 0 {
-        self.x += 1;
-      }
-
-      // Shift to the left if it's out of the right boundary
-      while self.x + self.shape.width > board.width {
-        if self.x > 0 {
-          self.x -= 1;
-        } else {
-          break;
-        }
-      }
+  // Three-corner T-spin check around the T's center cell. Full when both corners on the side the
+  // T points to are filled, Mini otherwise. Walls and the floor count as filled.
+  pub fn t_spin(&self, board: &Board) -> SpinType {
+    if self.kind != PieceKind::T {
+      return SpinType::None;
     }
 
-    // After shifting, check if the piece is valid
-    if !self.can_stay(board) {
-      // Revert if the rotation cannot fit
-      self.shape = original_shape;
+    let shape = self.shape();
+    let is_cell = |x: i8, y: i8| {
+      x >= 0
+        && y >= 0
+        && (x as u8) < shape.width
+        && (y as u8) < shape.height
+        && shape.cells[y as usize][x as usize] == 1
+    };
+    let is_filled = |x: i8, y: i8| {
+      let board_x = self.x as i8 + x;
+      let board_y = self.y as i8 + y;
+
+      board_x < 0
+        || board_y < 0
+        || board_x >= board.width as i8
+        || board_y >= board.height as i8
+        || board.is_filled(board_x as usize, board_y as usize)
+    };
+
+    // The center is the only cell with three neighbours in the piece
+    let directions = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let Some((center_x, center_y)) = shape
+      .iter_height()
+      .flat_map(|y| shape.iter_width().map(move |x| (x as i8, y as i8)))
+      .find(|&(x, y)| {
+        is_cell(x, y)
+          && directions
+            .iter()
+            .filter(|(dx, dy)| is_cell(x + dx, y + dy))
+            .count()
+            == 3
+      })
+    else {
+      return SpinType::None;
+    };
+
+    let corners = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+    let filled_corners = corners
+      .iter()
+      .filter(|(dx, dy)| is_filled(center_x + dx, center_y + dy))
+      .count();
+    if filled_corners < 3 {
+      return SpinType::None;
     }
-  }
 
-  // Check if the piece is within the board boundaries
-  fn is_within_bounds(&self, board: &Board) -> bool {
-    self.x as i8 >= 0 && self.x + self.shape.width <= board.width
+    // The T points away from the side without a neighbour
+    let (back_x, back_y) = directions
+      .iter()
+      .copied()
+      .find(|(dx, dy)| !is_cell(center_x + dx, center_y + dy))
+      .unwrap();
+    let front_filled = corners
+      .iter()
+      .filter(|(dx, dy)| *dx != back_x && *dy != back_y)
+      .all(|(dx, dy)| is_filled(center_x + dx, center_y + dy));
+
+    if front_filled {
+      SpinType::Full
+    } else {
+      SpinType::Mini
+    }
   }
 
   // Check if the piece can stay in its current position
   pub fn can_stay(&self, board: &Board) -> bool {
-    for y in self.shape.iter_height() {
-      for x in self.shape.iter_width() {
-        if self.shape.cells[y][x] == 1 {
-          let board_x = self.x as i8 + x as i8;
-          let board_y = self.y as i8 + y as i8;
-
-          // Check boundaries
-          if board_x < 0 || board_x >= board.width as i8 || board_y >= board.height as i8 {
-            return false;
-          }
-
-          // Check overlaps
-          if board_y >= 0 && board.grid[board_y as usize][board_x as usize] != Color::None {
-            return false;
-          }
-        }
-      }
-    }
-    true
+    board.fits(self.shape(), self.x as i32, self.y as i32)
   }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
   Left,
   Right,
@@ -0,0 +1,29 @@
+use crate::piece::Piece;
+use crate::shape::PieceKind;
+use rand::Rng;
+
+// How a game draws its pieces
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Randomizer {
+  Uniform,             // Every piece is any of the seven kinds, whatever came before
+  Bag(Vec<PieceKind>), // Shuffled bags of all seven kinds, holding what is left of the current one
+}
+
+impl Randomizer {
+  // A 7-bag starting with a fresh bag
+  pub fn seven_bag() -> Self {
+    Randomizer::Bag(Vec::new())
+  }
+
+  pub fn next_piece(&mut self, rng: &mut impl Rng) -> Piece {
+    match self {
+      Randomizer::Uniform => Piece::random_piece(rng),
+      Randomizer::Bag(bag) => {
+        if bag.is_empty() {
+          bag.extend(PieceKind::ALL);
+        }
+        Piece::new(bag.swap_remove(rng.gen_range(0..bag.len())))
+      }
+    }
+  }
+}
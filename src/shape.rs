@@ -1,34 +1,66 @@
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use crate::board::Row;
 use std::ops::Range;
+use wasm_bindgen::prelude::*;
 
-#[derive(Clone)]
+// One rotation state of a piece, its cells packed into the top left of a 4x4 box
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Shape {
-  pub cells: Vec<Vec<u8>>, // 2D matrix representation of the shape
+  pub cells: [[u8; 4]; 4], // 2D matrix representation of the shape
   pub width: u8,
   pub height: u8,
   pub color: Color,
+  masks: [u8; 4], // Each row of cells as bits, bit x set for column x
 }
 
 impl Shape {
-  // Rotate the shape clockwise
-  pub fn rotate(&self) -> Shape {
-    let new_cells = self
-      .iter_width()
-      .map(|x| {
-        self
-          .iter_height()
-          .map(|y| self.cells[(self.height - 1) as usize - y][x])
-          .collect()
-      })
-      .collect();
+  const fn new(cells: [[u8; 4]; 4], width: u8, height: u8, color: Color) -> Shape {
+    let mut masks = [0; 4];
+    let mut y = 0;
+    while y < 4 {
+      let mut x = 0;
+      while x < 4 {
+        masks[y] |= cells[y][x] << x;
+        x += 1;
+      }
+      y += 1;
+    }
 
     Shape {
-      cells: new_cells,
-      width: self.height,
-      height: self.width,
-      color: self.color,
+      cells,
+      width,
+      height,
+      color,
+      masks,
+    }
+  }
+
+  // The shape turned clockwise
+  const fn rotate(&self) -> Shape {
+    let mut cells = [[0; 4]; 4];
+    let mut y = 0;
+    while y < self.width as usize {
+      let mut x = 0;
+      while x < self.height as usize {
+        cells[y][x] = self.cells[self.height as usize - 1 - x][y];
+        x += 1;
+      }
+      y += 1;
     }
+
+    Shape::new(cells, self.height, self.width, self.color)
+  }
+
+  // All four rotation states, clockwise from this one
+  const fn rotations(self) -> [Shape; 4] {
+    let east = self.rotate();
+    let south = east.rotate();
+    let west = south.rotate();
+    [self, east, south, west]
+  }
+
+  // The cells of one row as bits, bit x set for column x
+  pub fn row_mask(&self, y: usize) -> Row {
+    self.masks[y] as Row
   }
 
   pub fn iter_height(&self) -> Range<usize> {
@@ -40,70 +72,111 @@ impl Shape {
   }
 }
 
-pub fn get_random_shape<'a>() -> Shape {
-  let mut rng = thread_rng();
+// The seven tetrominoes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+  O,
+  I,
+  T,
+  S,
+  Z,
+  J,
+  L,
+}
+
+// Rotation states of every kind, in the order of PieceKind
+static SHAPES: [[Shape; 4]; 7] = [
+  Shape::new(
+    [[1, 1, 0, 0], [1, 1, 0, 0], [0; 4], [0; 4]],
+    2,
+    2,
+    Color::Yellow,
+  )
+  .rotations(),
+  Shape::new([[1, 1, 1, 1], [0; 4], [0; 4], [0; 4]], 4, 1, Color::Cyan).rotations(),
+  Shape::new(
+    [[0, 1, 0, 0], [1, 1, 1, 0], [0; 4], [0; 4]],
+    3,
+    2,
+    Color::Purple,
+  )
+  .rotations(),
+  Shape::new(
+    [[0, 1, 1, 0], [1, 1, 0, 0], [0; 4], [0; 4]],
+    3,
+    2,
+    Color::Green,
+  )
+  .rotations(),
+  Shape::new(
+    [[1, 1, 0, 0], [0, 1, 1, 0], [0; 4], [0; 4]],
+    3,
+    2,
+    Color::Red,
+  )
+  .rotations(),
+  Shape::new(
+    [[1, 0, 0, 0], [1, 1, 1, 0], [0; 4], [0; 4]],
+    3,
+    2,
+    Color::Blue,
+  )
+  .rotations(),
+  Shape::new(
+    [[0, 0, 1, 0], [1, 1, 1, 0], [0; 4], [0; 4]],
+    3,
+    2,
+    Color::Orange,
+  )
+  .rotations(),
+];
 
-  if let Some(new_shape) = get_tetris_shapes().choose(&mut rng) {
-    new_shape.clone()
-  } else {
-    panic!("No shapes!")
+impl PieceKind {
+  pub const ALL: [PieceKind; 7] = [
+    PieceKind::O,
+    PieceKind::I,
+    PieceKind::T,
+    PieceKind::S,
+    PieceKind::Z,
+    PieceKind::J,
+    PieceKind::L,
+  ];
+
+  // The shape after the given clockwise quarter turns from spawn
+  pub fn shape(self, rotation: u8) -> &'static Shape {
+    &SHAPES[self as usize][rotation as usize % 4]
   }
-}
 
-pub fn get_tetris_shapes() -> Vec<Shape> {
-  vec![
-    // O shape
-    Shape {
-      cells: vec![vec![1, 1], vec![1, 1]],
-      width: 2,
-      height: 2,
-      color: Color::Yellow,
-    },
-    // I shape
-    Shape {
-      cells: vec![vec![1, 1, 1, 1]],
-      width: 4,
-      height: 1,
-      color: Color::Cyan,
-    },
-    // T shape
-    Shape {
-      cells: vec![vec![0, 1, 0], vec![1, 1, 1]],
-      width: 3,
-      height: 2,
-      color: Color::Purple,
-    },
-    // S shape
-    Shape {
-      cells: vec![vec![0, 1, 1], vec![1, 1, 0]],
-      width: 3,
-      height: 2,
-      color: Color::Green,
-    },
-    // Z shape
-    Shape {
-      cells: vec![vec![1, 1, 0], vec![0, 1, 1]],
-      width: 3,
-      height: 2,
-      color: Color::Red,
-    },
-    // J shape
-    Shape {
-      cells: vec![vec![1, 0, 0], vec![1, 1, 1]],
-      width: 3,
-      height: 2,
-      color: Color::Blue,
-    },
-    // L shape
-    Shape {
-      cells: vec![vec![0, 0, 1], vec![1, 1, 1]],
-      width: 3,
-      height: 2,
-      color: Color::Orange,
-    },
-  ]
+  pub fn color(self) -> Color {
+    self.shape(0).color
+  }
+
+  pub fn from_color(color: Color) -> Option<PieceKind> {
+    PieceKind::ALL
+      .into_iter()
+      .find(|kind| kind.color() == color)
+  }
+
+  pub fn letter(self) -> char {
+    match self {
+      PieceKind::O => 'O',
+      PieceKind::I => 'I',
+      PieceKind::T => 'T',
+      PieceKind::S => 'S',
+      PieceKind::Z => 'Z',
+      PieceKind::J => 'J',
+      PieceKind::L => 'L',
+    }
+  }
+
+  pub fn from_letter(letter: char) -> Option<PieceKind> {
+    PieceKind::ALL
+      .into_iter()
+      .find(|kind| kind.letter() == letter.to_ascii_uppercase())
+  }
 }
 
+#[wasm_bindgen]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Color {
   Cyan,
@@ -113,11 +186,12 @@ pub enum Color {
   Red,
   Blue,
   Orange,
-  None, // Represents an empty cell in the grid
+  Garbage, // Rows pushed up from the bottom of the board
+  None,    // Represents an empty cell in the grid
 }
 
 impl Color {
-  pub fn to_rgba(&self, opacity: f64) -> String {
+  pub fn to_rgba(self, opacity: f64) -> String {
     match self {
       Color::Cyan => format!("rgba(0, 255, 255, {})", opacity), // Cyan
       Color::Yellow => format!("rgba(255, 255, 0, {})", opacity), // Yellow
@@ -126,7 +200,88 @@ impl Color {
       Color::Red => format!("rgba(255, 69, 0, {})", opacity),   // Red (slightly darker)
       Color::Blue => format!("rgba(30, 144, 255, {})", opacity), // Dodger Blue
       Color::Orange => format!("rgba(255, 140, 0, {})", opacity), // Dark Orange
+      Color::Garbage => format!("rgba(150, 150, 150, {})", opacity), // Garbage (gray)
       Color::None => format!("rgba(50, 50, 50, {})", opacity),  // Empty cell (light gray)
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // The spawn cells as rows of their bounding box, as the shapes used to be listed
+  fn spawn_cells(kind: PieceKind) -> Vec<Vec<u8>> {
+    match kind {
+      PieceKind::O => vec![vec![1, 1], vec![1, 1]],
+      PieceKind::I => vec![vec![1, 1, 1, 1]],
+      PieceKind::T => vec![vec![0, 1, 0], vec![1, 1, 1]],
+      PieceKind::S => vec![vec![0, 1, 1], vec![1, 1, 0]],
+      PieceKind::Z => vec![vec![1, 1, 0], vec![0, 1, 1]],
+      PieceKind::J => vec![vec![1, 0, 0], vec![1, 1, 1]],
+      PieceKind::L => vec![vec![0, 0, 1], vec![1, 1, 1]],
+    }
+  }
+
+  // The clockwise turn shapes used to make at runtime, before the rotation table
+  fn rotate(cells: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let height = cells.len();
+    (0..cells[0].len())
+      .map(|x| (0..height).map(|y| cells[height - 1 - y][x]).collect())
+      .collect()
+  }
+
+  #[test]
+  fn table_matches_runtime_rotation() {
+    for kind in PieceKind::ALL {
+      let mut cells = spawn_cells(kind);
+      for rotation in 0..4 {
+        let shape = kind.shape(rotation);
+        let context = format!("{kind:?} rotation {rotation}");
+        assert_eq!(shape.height as usize, cells.len(), "{context}");
+        assert_eq!(shape.width as usize, cells[0].len(), "{context}");
+        assert_eq!(shape.color, kind.color(), "{context}");
+
+        for y in 0..4 {
+          for x in 0..4 {
+            let cell = cells.get(y).and_then(|row| row.get(x)).copied();
+            assert_eq!(
+              shape.cells[y][x],
+              cell.unwrap_or(0),
+              "{context} at {x}, {y}"
+            );
+          }
+
+          let mask = cells.get(y).map_or(0, |row| {
+            row
+              .iter()
+              .enumerate()
+              .fold(0, |mask, (x, &cell)| mask | (cell as Row) << x)
+          });
+          assert_eq!(shape.row_mask(y), mask, "{context} row {y}");
+        }
+
+        cells = rotate(&cells);
+      }
+    }
+  }
+
+  #[test]
+  fn four_turns_come_back_around() {
+    for kind in PieceKind::ALL {
+      for rotation in 0..4 {
+        let shape = kind.shape(rotation);
+        assert_eq!(
+          shape.rotate().rotate().rotate().rotate(),
+          *shape,
+          "{kind:?} {rotation}"
+        );
+        assert_eq!(
+          shape.rotate(),
+          *kind.shape(rotation + 1),
+          "{kind:?} {rotation}"
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,156 @@
+use crate::game_event::GameEvent;
+use crate::perfect_clear::PcStep;
+use crate::piece::Piece;
+use crate::shape::Color;
+use wasm_bindgen::prelude::*;
+use web_sys::js_sys::{Array, Function, Object, Reflect};
+
+// Read-only copy of a piece handed out to JS
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct PieceView {
+  color: Color,
+  x: u8,
+  y: u8,
+  width: u8,
+  height: u8,
+  cells: Vec<u8>,
+}
+
+impl PieceView {
+  pub fn from_piece(piece: &Piece) -> Self {
+    let shape = piece.shape();
+    PieceView {
+      color: shape.color,
+      x: piece.x,
+      y: piece.y,
+      width: shape.width,
+      height: shape.height,
+      cells: shape
+        .iter_height()
+        .flat_map(|y| shape.iter_width().map(move |x| shape.cells[y][x]))
+        .collect(),
+    }
+  }
+}
+
+#[wasm_bindgen]
+impl PieceView {
+  #[wasm_bindgen(getter)]
+  pub fn color(&self) -> Color {
+    self.color
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn x(&self) -> u8 {
+    self.x
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn y(&self) -> u8 {
+    self.y
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn width(&self) -> u8 {
+    self.width
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn height(&self) -> u8 {
+    self.height
+  }
+
+  // Shape cells row by row, 1 for occupied and 0 for empty
+  #[wasm_bindgen(getter)]
+  pub fn cells(&self) -> Vec<u8> {
+    self.cells.clone()
+  }
+}
+
+// One placement of a perfect clear handed out to JS
+#[wasm_bindgen]
+pub struct PcStepView {
+  hold: bool,
+  piece: PieceView,
+}
+
+impl PcStepView {
+  pub fn from_step(step: &PcStep) -> Self {
+    PcStepView {
+      hold: step.hold,
+      piece: PieceView::from_piece(&step.piece),
+    }
+  }
+}
+
+#[wasm_bindgen]
+impl PcStepView {
+  // Hold is pressed before the piece is played
+  #[wasm_bindgen(getter)]
+  pub fn hold(&self) -> bool {
+    self.hold
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn piece(&self) -> PieceView {
+    self.piece.clone()
+  }
+}
+
+// Plain JS object for an event, e.g. { type: "LinesCleared", rows: [18, 19], clearType: "Double" }
+pub fn event_to_js(event: &GameEvent) -> JsValue {
+  let object = Object::new();
+  set_field(&object, "type", &event.name().into());
+
+  match event {
+    GameEvent::PieceSpawned { color } => {
+      set_field(&object, "color", &format!("{:?}", color).into());
+    }
+    GameEvent::Moved { direction } => {
+      set_field(&object, "direction", &format!("{:?}", direction).into());
+    }
+    GameEvent::Locked { color, x, y } => {
+      set_field(&object, "color", &format!("{:?}", color).into());
+      set_field(&object, "x", &(*x).into());
+      set_field(&object, "y", &(*y).into());
+    }
+    GameEvent::LinesCleared {
+      rows,
+      clear_type,
+      spin,
+    } => {
+      let rows: Array = rows.iter().map(|&row| JsValue::from(row)).collect();
+      set_field(&object, "rows", &rows);
+      set_field(&object, "clearType", &format!("{:?}", clear_type).into());
+      set_field(&object, "spin", &format!("{:?}", spin).into());
+    }
+    GameEvent::LevelUp { level } => {
+      set_field(&object, "level", &(*level).into());
+    }
+    GameEvent::FinesseFault {
+      key_presses,
+      optimal,
+    } => {
+      set_field(&object, "keyPresses", &(*key_presses).into());
+      set_field(&object, "optimal", &(*optimal).into());
+    }
+    GameEvent::Rotated | GameEvent::Hold | GameEvent::GameOver | GameEvent::Completed => {}
+  }
+
+  object.into()
+}
+
+// Hand every event to every listener, in order
+pub fn dispatch_events(events: &[GameEvent], listeners: &[Function]) {
+  for event in events {
+    let value = event_to_js(event);
+    for listener in listeners {
+      listener.call1(&JsValue::NULL, &value).ok();
+    }
+  }
+}
+
+fn set_field(object: &Object, key: &str, value: &JsValue) {
+  Reflect::set(object, &key.into(), value).ok();
+}
@@ -0,0 +1,271 @@
+mod bot;
+mod frontend;
+mod message;
+
+pub use bot::{Position, TbpBot};
+pub use frontend::{play_move, start_message};
+pub use message::{
+  BotMessage, FrontendMessage, Location, Move, Orientation, Spin, Start, TbpPiece,
+};
+
+use crate::board::Board;
+use crate::game_event::SpinType;
+use crate::piece::Piece;
+use crate::shape::{Color, PieceKind};
+
+// Rows in a protocol board, bottom row first. Rows above our own board are always empty.
+pub const TBP_BOARD_HEIGHT: usize = 40;
+
+const ORIENTATIONS: [Orientation; 4] = [
+  Orientation::North,
+  Orientation::East,
+  Orientation::South,
+  Orientation::West,
+];
+
+impl TbpPiece {
+  pub fn from_color(color: Color) -> Option<TbpPiece> {
+    match color {
+      Color::Cyan => Some(TbpPiece::I),
+      Color::Yellow => Some(TbpPiece::O),
+      Color::Purple => Some(TbpPiece::T),
+      Color::Green => Some(TbpPiece::S),
+      Color::Red => Some(TbpPiece::Z),
+      Color::Blue => Some(TbpPiece::J),
+      Color::Orange => Some(TbpPiece::L),
+      Color::Garbage | Color::None => None,
+    }
+  }
+
+  pub fn color(self) -> Color {
+    match self {
+      TbpPiece::I => Color::Cyan,
+      TbpPiece::O => Color::Yellow,
+      TbpPiece::T => Color::Purple,
+      TbpPiece::S => Color::Green,
+      TbpPiece::Z => Color::Red,
+      TbpPiece::J => Color::Blue,
+      TbpPiece::L => Color::Orange,
+    }
+  }
+
+  // The piece the way it spawns, pointing north
+  pub fn spawn(self) -> Piece {
+    Piece::new(self.kind())
+  }
+
+  pub fn kind(self) -> PieceKind {
+    PieceKind::from_color(self.color()).unwrap()
+  }
+
+  // Cells around the rotation centre, x to the right and y up
+  fn offsets(self, orientation: Orientation) -> [(i32, i32); 4] {
+    let mut offsets = match self {
+      TbpPiece::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+      TbpPiece::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+      TbpPiece::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+      TbpPiece::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+      TbpPiece::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+      TbpPiece::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+      TbpPiece::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+    };
+
+    // Clockwise quarter turns
+    for _ in 0..orientation.rotations() {
+      offsets = offsets.map(|(x, y)| (y, -x));
+    }
+    offsets
+  }
+}
+
+impl Orientation {
+  pub fn rotations(self) -> usize {
+    ORIENTATIONS.iter().position(|&o| o == self).unwrap()
+  }
+}
+
+impl From<SpinType> for Spin {
+  fn from(spin: SpinType) -> Self {
+    match spin {
+      SpinType::None => Spin::None,
+      SpinType::Mini => Spin::Mini,
+      SpinType::Full => Spin::Full,
+    }
+  }
+}
+
+// Where the protocol puts a piece of ours. Our pieces are anchored at the top left of their
+// cells and the protocol's at the rotation centre counted from the bottom.
+pub fn location(piece: &Piece, board: &Board) -> Option<Location> {
+  let kind = TbpPiece::from_color(piece.shape().color)?;
+  let orientation = ORIENTATIONS[piece.rotation as usize];
+
+  let offsets = kind.offsets(orientation);
+  let left = offsets.iter().map(|&(x, _)| x).min().unwrap();
+  let top = offsets.iter().map(|&(_, y)| y).max().unwrap();
+
+  Some(Location {
+    kind,
+    orientation,
+    x: piece.x as i32 - left,
+    y: board.height as i32 - 1 - piece.y as i32 - top,
+  })
+}
+
+// The piece at a protocol location, None if it does not fit on the board
+pub fn piece_at(location: &Location, board: &Board) -> Option<Piece> {
+  let rotation = location.orientation.rotations() as u8;
+  let shape = location.kind.kind().shape(rotation);
+  let offsets = location.kind.offsets(location.orientation);
+  let left = offsets.iter().map(|&(x, _)| x).min().unwrap();
+  let top = offsets.iter().map(|&(_, y)| y).max().unwrap();
+
+  let x = u8::try_from(location.x + left).ok()?;
+  let y = u8::try_from(board.height as i32 - 1 - (location.y + top)).ok()?;
+  let fits = x + shape.width <= board.width && y + shape.height <= board.height;
+
+  fits.then_some(Piece {
+    kind: location.kind.kind(),
+    rotation,
+    x,
+    y,
+  })
+}
+
+// Board cells as protocol rows, bottom row first, with the piece letter or "G" for garbage
+pub fn board_rows(board: &Board) -> Vec<Vec<Option<char>>> {
+  (0..TBP_BOARD_HEIGHT)
+    .map(|row| {
+      let y = (board.height as usize).checked_sub(row + 1);
+      board
+        .iter_width()
+        .map(|x| y.and_then(|y| cell_letter(board.cell(x, y))))
+        .collect()
+    })
+    .collect()
+}
+
+// Our board for protocol rows. It is tall enough for every row the protocol can send.
+pub fn board_from_rows(rows: &[Vec<Option<char>>]) -> Board {
+  let width = rows.first().map_or(10, |row| row.len().max(4)) as u8;
+  let mut board = Board::new(width, TBP_BOARD_HEIGHT as u8);
+
+  for (row, cells) in rows.iter().enumerate().take(TBP_BOARD_HEIGHT) {
+    let y = board.height as usize - 1 - row;
+    for (x, &cell) in cells.iter().enumerate().take(width as usize) {
+      board.set_cell(x, y, cell.map_or(Color::None, cell_color));
+    }
+  }
+
+  board
+}
+
+fn cell_letter(color: Color) -> Option<char> {
+  match color {
+    Color::Cyan => Some('I'),
+    Color::Yellow => Some('O'),
+    Color::Purple => Some('T'),
+    Color::Green => Some('S'),
+    Color::Red => Some('Z'),
+    Color::Blue => Some('J'),
+    Color::Orange => Some('L'),
+    Color::Garbage => Some('G'),
+    Color::None => None,
+  }
+}
+
+fn cell_color(letter: char) -> Color {
+  match letter {
+    'I' => Color::Cyan,
+    'O' => Color::Yellow,
+    'T' => Color::Purple,
+    'S' => Color::Green,
+    'Z' => Color::Red,
+    'J' => Color::Blue,
+    'L' => Color::Orange,
+    _ => Color::Garbage,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Board cells a piece covers as (column, row from the top)
+  fn cells(piece: &Piece) -> Vec<(i32, i32)> {
+    let shape = piece.shape();
+    let mut cells: Vec<(i32, i32)> = shape
+      .iter_height()
+      .flat_map(|dy| shape.iter_width().map(move |dx| (dx, dy)))
+      .filter(|&(dx, dy)| shape.cells[dy][dx] == 1)
+      .map(|(dx, dy)| (piece.x as i32 + dx as i32, piece.y as i32 + dy as i32))
+      .collect();
+    cells.sort();
+    cells
+  }
+
+  #[test]
+  fn locations_round_trip() {
+    let board = Board::new(10, 20);
+
+    for kind in PieceKind::ALL {
+      for rotation in 0..4 {
+        let shape = kind.shape(rotation);
+        for (x, y) in [(0, 0), (10 - shape.width, 20 - shape.height), (3, 7)] {
+          let piece = Piece {
+            kind,
+            rotation,
+            x,
+            y,
+          };
+          let location = location(&piece, &board).unwrap();
+          assert_eq!(location.orientation, ORIENTATIONS[rotation as usize]);
+          assert_eq!(location.kind.kind(), kind);
+
+          // The protocol's cells around the centre, counted from the bottom, are the piece's
+          let mut protocol_cells: Vec<(i32, i32)> = location
+            .kind
+            .offsets(location.orientation)
+            .iter()
+            .map(|&(dx, dy)| (location.x + dx, 19 - (location.y + dy)))
+            .collect();
+          protocol_cells.sort();
+          assert_eq!(protocol_cells, cells(&piece), "{location:?}");
+
+          let found = piece_at(&location, &board).unwrap();
+          assert_eq!(
+            (found.kind, found.rotation, found.x, found.y),
+            (piece.kind, piece.rotation, piece.x, piece.y),
+            "{location:?}"
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn spawned_t_as_json() {
+    let board = Board::new(10, 20);
+    let piece = Piece {
+      kind: PieceKind::T,
+      rotation: 0,
+      x: 3,
+      y: 0,
+    };
+
+    let json = serde_json::to_string(&location(&piece, &board).unwrap()).unwrap();
+    assert_eq!(json, r#"{"type":"T","orientation":"north","x":4,"y":18}"#);
+  }
+
+  #[test]
+  fn locations_off_the_board() {
+    let board = Board::new(10, 20);
+    let location = Location {
+      kind: TbpPiece::I,
+      orientation: Orientation::North,
+      x: 8,
+      y: 0,
+    };
+    assert!(piece_at(&location, &board).is_none());
+  }
+}
@@ -0,0 +1,137 @@
+use crate::board::Board;
+use crate::bot::Bot;
+use crate::game_state::GameState;
+use crate::piece::Piece;
+use crate::tbp::{
+  board_from_rows, location, piece_at, BotMessage, FrontendMessage, Move, Spin, Start, TbpPiece,
+};
+use std::collections::VecDeque;
+
+// One of our bots behind the protocol, so any protocol frontend can play against it
+pub struct TbpBot {
+  pub bot: Bot,
+  position: Option<Position>,
+}
+
+// What the bot knows about the game: the board, the pieces revealed so far and the hold
+#[derive(Clone)]
+pub struct Position {
+  pub board: Board,
+  pub queue: VecDeque<Piece>, // Starts with the piece to play now
+  pub held_piece: Option<Piece>,
+}
+
+impl TbpBot {
+  pub fn new(bot: Bot) -> Self {
+    TbpBot {
+      bot,
+      position: None,
+    }
+  }
+
+  // Sent once when the bot starts, before any message from the frontend
+  pub fn info() -> BotMessage {
+    BotMessage::Info {
+      name: "Rustris".to_string(),
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      author: "Rustris contributors".to_string(),
+      features: Vec::new(),
+    }
+  }
+
+  // React to a frontend message, returning the reply if the protocol expects one
+  pub fn handle(&mut self, message: FrontendMessage) -> Option<BotMessage> {
+    match message {
+      FrontendMessage::Rules => Some(BotMessage::Ready),
+      FrontendMessage::Start(start) => {
+        self.position = Some(Position::from_start(&start));
+        None
+      }
+      FrontendMessage::Suggest => Some(BotMessage::Suggestion {
+        moves: self.suggest().into_iter().collect(),
+      }),
+      FrontendMessage::Play { tbp_move } => {
+        if let Some(position) = self.position.as_mut() {
+          position.play(&tbp_move);
+        }
+        None
+      }
+      FrontendMessage::NewPiece { piece } => {
+        if let Some(position) = self.position.as_mut() {
+          position.queue.push_back(piece.spawn());
+        }
+        None
+      }
+      FrontendMessage::Stop | FrontendMessage::Quit => {
+        self.position = None;
+        None
+      }
+    }
+  }
+
+  fn suggest(&self) -> Option<Move> {
+    let game_state = self.position.as_ref()?.game_state()?;
+    let placement = self.bot.best_placement(&game_state)?;
+
+    Some(Move {
+      location: location(&placement.target.piece, &game_state.board)?,
+      spin: Spin::from(placement.target.spin),
+    })
+  }
+}
+
+impl Position {
+  pub fn from_start(start: &Start) -> Self {
+    Position {
+      board: board_from_rows(&start.board),
+      queue: start.queue.iter().map(|piece| piece.spawn()).collect(),
+      held_piece: start.hold.map(TbpPiece::spawn),
+    }
+  }
+
+  pub fn from_game_state(game_state: &GameState) -> Self {
+    let mut queue = VecDeque::from([
+      game_state.current_piece.clone(),
+      game_state.next_piece.clone(),
+    ]);
+    queue.extend(game_state.preview.iter().cloned());
+
+    Position {
+      board: game_state.board.clone(),
+      queue,
+      held_piece: game_state.held_piece.clone(),
+    }
+  }
+
+  // A game state for the bots to search from, None until two pieces are known
+  pub fn game_state(&self) -> Option<GameState> {
+    let queue: Vec<Piece> = self.queue.iter().cloned().collect();
+    GameState::from_position(self.board.clone(), &queue, self.held_piece.clone())
+  }
+
+  // Lock the piece where the move puts it. A piece other than the current one was held for.
+  pub fn play(&mut self, tbp_move: &Move) {
+    let Some(piece) = piece_at(&tbp_move.location, &self.board) else {
+      return;
+    };
+
+    let is_current = self
+      .queue
+      .front()
+      .is_some_and(|current| current.kind == piece.kind);
+
+    if is_current {
+      self.queue.pop_front();
+    } else if self.held_piece.is_some() {
+      // Played the held piece, the current one takes its place
+      self.held_piece = self.queue.pop_front();
+    } else {
+      // Held the current piece and played the next one
+      self.held_piece = self.queue.pop_front();
+      self.queue.pop_front();
+    }
+
+    self.board.merge_piece(&piece);
+    self.board.clear_full_lines();
+  }
+}
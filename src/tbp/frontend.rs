@@ -0,0 +1,89 @@
+use crate::bot::{piece_after_hold, placement_path, Placement};
+use crate::game_event::SpinType;
+use crate::game_state::GameState;
+use crate::movegen::{covers_same_cells, locked_placements};
+use crate::tbp::{board_rows, piece_at, FrontendMessage, Move, Position, Spin, Start, TbpPiece};
+
+// The whole position, sent to a bot when a game starts or after the board changed in a way the
+// bot cannot know about, like garbage rising
+pub fn start_message(game_state: &GameState) -> FrontendMessage {
+  let queue = [&game_state.current_piece, &game_state.next_piece]
+    .into_iter()
+    .chain(game_state.preview.iter())
+    .filter_map(|piece| TbpPiece::from_color(piece.shape().color))
+    .collect();
+
+  FrontendMessage::Start(Start {
+    hold: game_state
+      .held_piece
+      .as_ref()
+      .and_then(|piece| TbpPiece::from_color(piece.shape().color)),
+    queue,
+    combo: game_state.combo(),
+    back_to_back: game_state.is_back_to_back(),
+    board: board_rows(&game_state.board),
+  })
+}
+
+// Play a bot's move through the regular inputs, holding when it names the hold piece. Returns the
+// messages that keep the bot in sync with the game.
+pub fn play_move(
+  game_state: &mut GameState,
+  tbp_move: &Move,
+) -> Result<Vec<FrontendMessage>, String> {
+  let target = piece_at(&tbp_move.location, &game_state.board).ok_or("Move is off the board")?;
+
+  let hold = game_state.current_piece.kind != target.kind;
+  let piece = if hold {
+    piece_after_hold(game_state)
+      .filter(|piece| !game_state.hold_used && piece.kind == target.kind)
+      .ok_or("Move is for a piece that is not available")?
+  } else {
+    game_state.current_piece.clone()
+  };
+
+  let mut target = locked_placements(&piece, &game_state.board)
+    .into_iter()
+    .find(|placement| covers_same_cells(&placement.piece, &target))
+    .ok_or("Move cannot be reached on our rules")?;
+
+  // Without a spin asked for, any path that ends on the target will do
+  if tbp_move.spin == Spin::None {
+    target.spin = SpinType::None;
+  }
+
+  // Holding into an empty hold reveals two pieces instead of one
+  let revealed = if hold && game_state.held_piece.is_none() {
+    2
+  } else {
+    1
+  };
+
+  let mut expected = Position::from_game_state(game_state);
+  expected.play(tbp_move);
+
+  let placement = Placement {
+    hold,
+    target,
+    score: 0.0,
+  };
+  for key_press in placement_path(game_state, &placement) {
+    game_state.apply_key_press(key_press);
+  }
+
+  if expected.board.rows() != game_state.board.rows() {
+    return Ok(vec![FrontendMessage::Stop, start_message(game_state)]);
+  }
+
+  let mut messages = vec![FrontendMessage::Play {
+    tbp_move: *tbp_move,
+  }];
+  let new_pieces = game_state.preview.iter().rev().take(revealed).rev();
+  messages.extend(
+    new_pieces
+      .filter_map(|piece| TbpPiece::from_color(piece.shape().color))
+      .map(|piece| FrontendMessage::NewPiece { piece }),
+  );
+
+  Ok(messages)
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+// Messages of the Tetris Bot Protocol, one JSON object per line tagged by "type"
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TbpPiece {
+  I,
+  O,
+  T,
+  L,
+  J,
+  S,
+  Z,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+  North,
+  East,
+  South,
+  West,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+  None,
+  Mini,
+  Full,
+}
+
+// A piece's rotation centre, x from the left wall and y from the bottom row
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+  #[serde(rename = "type")]
+  pub kind: TbpPiece,
+  pub orientation: Orientation,
+  pub x: i32,
+  pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+  pub location: Location,
+  pub spin: Spin,
+}
+
+// Sent by the game to the bot
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+  Rules,
+  Start(Start),
+  Suggest,
+  Play {
+    #[serde(rename = "move")]
+    tbp_move: Move,
+  },
+  NewPiece {
+    piece: TbpPiece,
+  },
+  Stop,
+  Quit,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Start {
+  pub hold: Option<TbpPiece>,
+  pub queue: Vec<TbpPiece>, // Starts with the piece to play now
+  pub combo: u32,
+  pub back_to_back: bool,
+  pub board: Vec<Vec<Option<char>>>, // Bottom row first
+}
+
+// Sent by the bot to the game
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+  Info {
+    name: String,
+    version: String,
+    author: String,
+    features: Vec<String>,
+  },
+  Ready,
+  Error {
+    reason: String,
+  },
+  Suggestion {
+    moves: Vec<Move>,
+  },
+}
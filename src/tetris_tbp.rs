@@ -0,0 +1,46 @@
+use crate::bot::{BeamSearchBot, Bot};
+use crate::tbp::{FrontendMessage, TbpBot};
+use wasm_bindgen::prelude::*;
+
+// Keeps a search short enough to answer a suggest request without stalling the page
+const TIME_BUDGET_MILLIS: f64 = 50.0;
+
+// Our bot behind the Tetris Bot Protocol, e.g. inside a web worker for a protocol frontend.
+// Messages go in and out as JSON strings.
+#[wasm_bindgen]
+pub struct TetrisTbpBot {
+  tbp_bot: TbpBot,
+}
+
+#[wasm_bindgen]
+impl TetrisTbpBot {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> TetrisTbpBot {
+    let bot = Bot::BeamSearch(BeamSearchBot {
+      time_budget_millis: Some(TIME_BUDGET_MILLIS),
+      ..BeamSearchBot::default()
+    });
+
+    TetrisTbpBot {
+      tbp_bot: TbpBot::new(bot),
+    }
+  }
+
+  // The info message to send before anything else
+  pub fn info(&self) -> String {
+    serde_json::to_string(&TbpBot::info()).unwrap()
+  }
+
+  // The reply to a frontend message, if any. Messages that cannot be read are ignored.
+  pub fn handle_message(&mut self, message: &str) -> Option<String> {
+    let message = serde_json::from_str::<FrontendMessage>(message).ok()?;
+    let reply = self.tbp_bot.handle(message)?;
+    Some(serde_json::to_string(&reply).unwrap())
+  }
+}
+
+impl Default for TetrisTbpBot {
+  fn default() -> Self {
+    Self::new()
+  }
+}
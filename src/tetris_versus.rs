@@ -0,0 +1,205 @@
+use crate::board_canvas;
+use crate::bot::{BeamSearchBot, Bot, DEFAULT_MOVE_FRAMES};
+use crate::frame_loop;
+use crate::game_renderer::GameRenderer;
+use crate::input::{Input, KeyBindings};
+use crate::versus::Versus;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, KeyboardEvent};
+
+// Keeps a bot's search well inside a single frame
+const BOT_TIME_BUDGET_MILLIS: f64 = 8.0;
+
+// Two local players on one page, each on their own canvas and side of the keyboard
+#[wasm_bindgen]
+pub struct TetrisVersus {
+  game: Rc<RefCell<VersusGame>>,
+}
+
+struct VersusGame {
+  versus: Versus,
+  contexts: [CanvasRenderingContext2d; 2],
+  key_bindings: [KeyBindings; 2],
+  bots: [Option<Bot>; 2],
+  started: bool,
+}
+
+#[wasm_bindgen]
+impl TetrisVersus {
+  #[wasm_bindgen(constructor)]
+  pub fn new(
+    player_one_canvas_id: &str,
+    player_two_canvas_id: &str,
+  ) -> Result<TetrisVersus, JsValue> {
+    let versus = Versus::new(10, 20);
+    let (_, player_one_ctx) = board_canvas(player_one_canvas_id, &versus.players[0])?;
+    let (_, player_two_ctx) = board_canvas(player_two_canvas_id, &versus.players[1])?;
+
+    let game = VersusGame {
+      versus,
+      contexts: [player_one_ctx, player_two_ctx],
+      key_bindings: [KeyBindings::player_one(), KeyBindings::player_two()],
+      bots: [None, None],
+      started: false,
+    };
+
+    Ok(TetrisVersus {
+      game: Rc::new(RefCell::new(game)),
+    })
+  }
+
+  // Player is 0 or 1. Calls for any other player do nothing and return false.
+  pub fn bind_key(&self, player: usize, key: &str, input: Input) -> bool {
+    let mut game = self.game.borrow_mut();
+    let Some(key_bindings) = game.key_bindings.get_mut(player) else {
+      return false;
+    };
+    key_bindings.bind(key, input);
+    true
+  }
+
+  pub fn clear_key_bindings(&self, player: usize) -> bool {
+    let mut game = self.game.borrow_mut();
+    let Some(key_bindings) = game.key_bindings.get_mut(player) else {
+      return false;
+    };
+    key_bindings.clear();
+    true
+  }
+
+  // Hand a player over to the beam search bot, or back to the keyboard
+  pub fn set_bot(&self, player: usize, enabled: bool) -> bool {
+    let mut game = self.game.borrow_mut();
+    let Some(slot) = game.bots.get_mut(player) else {
+      return false;
+    };
+    *slot = enabled.then(|| {
+      Bot::BeamSearch(BeamSearchBot {
+        time_budget_millis: Some(BOT_TIME_BUDGET_MILLIS),
+        ..BeamSearchBot::default()
+      })
+    });
+    true
+  }
+
+  // Chance from 0 to 1 that the hole moves between rows of the same attack, for both players
+  pub fn set_garbage_messiness(&self, messiness: f32) {
+    for player in self.game.borrow_mut().versus.players.iter_mut() {
+      player.garbage.messiness = messiness.clamp(0.0, 1.0);
+    }
+  }
+
+  // Frames an attack waits before it can rise on the opponent's board
+  pub fn set_garbage_delay(&self, delay_frames: u64) {
+    for player in self.game.borrow_mut().versus.players.iter_mut() {
+      player.garbage.delay_frames = delay_frames;
+    }
+  }
+
+  // Start over with fresh boards and a new shared piece sequence
+  pub fn new_match(&self) {
+    let mut game = self.game.borrow_mut();
+    let versus = Versus::new(10, 20);
+
+    // Keep the garbage settings of the previous match
+    let garbage = &game.versus.players[0].garbage;
+    let (messiness, delay_frames) = (garbage.messiness, garbage.delay_frames);
+    game.versus = versus;
+    for player in game.versus.players.iter_mut() {
+      player.garbage.messiness = messiness;
+      player.garbage.delay_frames = delay_frames;
+    }
+
+    game.render();
+  }
+
+  pub fn winner(&self) -> Option<usize> {
+    self.game.borrow().versus.winner
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.game.borrow().versus.is_over()
+  }
+
+  // Undefined for a player other than 0 or 1
+  pub fn pending_garbage(&self, player: usize) -> Option<u32> {
+    let game = self.game.borrow();
+    let player = game.versus.players.get(player)?;
+    Some(player.garbage.pending_lines())
+  }
+
+  pub fn start_game(&self) {
+    if self.game.borrow().started {
+      return;
+    }
+    self.game.borrow_mut().started = true;
+
+    let tick_game_clone = Rc::clone(&self.game);
+    frame_loop::run(move || {
+      let mut game = tick_game_clone.borrow_mut();
+      game.versus.step_frame();
+      game.play_bots();
+      game.render();
+    });
+
+    // Both players share the window, each key is routed through both players' bindings
+    let game_clone = Rc::clone(&self.game);
+    let closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+      let mut game = game_clone.borrow_mut();
+      let key = event.key();
+      let mut handled = false;
+
+      for player in 0..2 {
+        if let Some(input) = game.key_bindings[player].input_for(&key) {
+          if event.repeat() {
+            game.versus.apply_repeated_input(player, input);
+          } else {
+            game.versus.apply_input(player, input);
+          }
+          handled = true;
+        }
+      }
+
+      if handled {
+        event.prevent_default();
+        game.render();
+      }
+    }) as Box<dyn FnMut(_)>);
+
+    web_sys::window()
+      .unwrap()
+      .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
+      .expect("Failed to add keydown listener");
+
+    closure.forget();
+  }
+}
+
+impl VersusGame {
+  fn play_bots(&mut self) {
+    for player in 0..2 {
+      let frame = self.versus.players[player].frame;
+      if let Some(bot) = &self.bots[player] {
+        if frame.is_multiple_of(DEFAULT_MOVE_FRAMES) {
+          self.versus.play_bot(player, bot);
+        }
+      }
+    }
+  }
+
+  fn render(&self) {
+    for (player, canvas) in self.contexts.iter().enumerate() {
+      GameRenderer::render(canvas, &self.versus.players[player]);
+
+      if self.versus.winner == Some(player) {
+        GameRenderer::render_banner(canvas, "Winner!", "lime");
+      } else if self.versus.is_over() && self.versus.winner.is_none() {
+        GameRenderer::render_banner(canvas, "Draw", "white");
+      }
+    }
+  }
+}
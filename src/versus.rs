@@ -0,0 +1,85 @@
+use crate::bot::Bot;
+use crate::game_state::GameState;
+use crate::input::Input;
+use crate::mode::GameMode;
+use rand::random;
+
+// Two boards playing the same piece sequence, sending garbage to each other
+pub struct Versus {
+  pub players: [GameState; 2],
+  pub winner: Option<usize>,
+}
+
+impl Versus {
+  pub fn new(board_width: u8, board_height: u8) -> Self {
+    let seed = random();
+    let new_player = || GameState::with_seed(board_width, board_height, GameMode::default(), seed);
+
+    Versus {
+      players: [new_player(), new_player()],
+      winner: None,
+    }
+  }
+
+  // Over as soon as either board tops out. Both topping out on the same frame is a draw.
+  pub fn is_over(&self) -> bool {
+    self.players.iter().any(|player| player.game_over)
+  }
+
+  pub fn apply_input(&mut self, player: usize, input: Input) {
+    if self.is_over() {
+      return;
+    }
+
+    self.update_player(player, |game_state| game_state.apply_input(input));
+  }
+
+  // An input from a key the player is still holding down
+  pub fn apply_repeated_input(&mut self, player: usize, input: Input) {
+    if self.is_over() {
+      return;
+    }
+
+    self.update_player(player, |game_state| game_state.apply_repeated_input(input));
+  }
+
+  // Let a bot place the player's current piece
+  pub fn play_bot(&mut self, player: usize, bot: &Bot) {
+    if self.is_over() {
+      return;
+    }
+
+    self.update_player(player, |game_state| {
+      bot.play(game_state);
+    });
+  }
+
+  pub fn step_frame(&mut self) {
+    if self.is_over() {
+      return;
+    }
+
+    for player in 0..self.players.len() {
+      self.update_player(player, GameState::step_frame);
+    }
+  }
+
+  // Run an action on one board and pass any attack it produced to the opponent
+  pub fn update_player<R>(&mut self, player: usize, action: impl FnOnce(&mut GameState) -> R) -> R {
+    let result = action(&mut self.players[player]);
+
+    let attack = self.players[player].take_outgoing_attack();
+    self.players[1 - player].receive_garbage(attack);
+
+    self.update_winner();
+    result
+  }
+
+  fn update_winner(&mut self) {
+    self.winner = match self.players.each_ref().map(|player| player.game_over) {
+      [true, false] => Some(1),
+      [false, true] => Some(0),
+      _ => None,
+    };
+  }
+}
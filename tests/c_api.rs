@@ -0,0 +1,42 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Builds examples/c/test_rustris.c against include/rustris.h and the static library cargo built
+// for this test, then runs it. Skipped when there is no C compiler; set CC to pick one.
+#[test]
+fn c_example_passes() {
+  let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+  if Command::new(&compiler).arg("--version").output().is_err() {
+    eprintln!("No C compiler at {compiler}, skipping the C API test");
+    return;
+  }
+
+  let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  // Test binaries run from target/<profile>/deps, next to the library's own output directory
+  let profile_dir = env::current_exe()
+    .unwrap()
+    .parent()
+    .and_then(|deps| deps.parent())
+    .unwrap()
+    .to_path_buf();
+  let program = profile_dir.join("test_rustris");
+
+  let status = Command::new(&compiler)
+    .arg(root.join("examples/c/test_rustris.c"))
+    .arg("-I")
+    .arg(root.join("include"))
+    .arg(profile_dir.join("librustris.a"))
+    .args(["-lm", "-lpthread", "-ldl", "-o"])
+    .arg(&program)
+    .status()
+    .unwrap();
+  assert!(status.success(), "the C example failed to build");
+
+  let output = Command::new(&program).output().unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+}